@@ -0,0 +1,319 @@
+use std::{
+    env,
+    fmt::Display,
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use todo::{
+    command::Command, error::ActionError, middleware::Middleware, outcome::Outcome,
+    task_manager::TaskManager,
+};
+
+pub const AUDIT_FILE: &str = "audit.log";
+
+/// Size the log can reach before it is rotated.
+const MAX_SIZE: u64 = 1024 * 1024;
+
+/// How many rotated logs are kept, as `audit.log.1` (newest) up to
+/// `audit.log.3` (oldest).
+const ROTATED_FILES: usize = 3;
+
+#[derive(Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub user: String,
+    pub timestamp: DateTime<Utc>,
+    pub command: String,
+    pub args: Vec<String>,
+    pub result: String,
+}
+
+impl AuditEntry {
+    /// Checks the entry against every word of the filter. A word can be
+    /// `user:`, `command:`, `result:` or `date:` followed by a value, or
+    /// plain text searched in the whole entry. Case is ignored.
+    pub fn matches(&self, filter: &str) -> bool {
+        let line = self.to_string().to_lowercase();
+
+        filter
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| match word.split_once(':') {
+                Some(("user", value)) => self.user.to_lowercase() == value,
+                Some(("command", value)) => self.command.to_lowercase() == value,
+                Some(("result", value)) => self.result.to_lowercase().contains(value),
+                Some(("date", value)) => self.timestamp.to_rfc3339().starts_with(value),
+                _ => line.contains(word),
+            })
+    }
+}
+
+impl Display for AuditEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} [{}] {}",
+            self.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            self.user,
+            self.command,
+            self.args.join(", "),
+            self.result
+        )
+    }
+}
+
+/// Middleware that appends every mutating command, with who ran it and
+/// how it went, to the audit log as one JSON line.
+pub struct AuditLog {
+    path: String,
+    user: String,
+}
+
+impl AuditLog {
+    pub fn new(path: &str) -> Self {
+        let user = env::var("USER")
+            .or_else(|_| env::var("USERNAME"))
+            .unwrap_or_else(|_| "unknown".to_string());
+
+        Self {
+            path: path.to_string(),
+            user,
+        }
+    }
+
+    fn append(&self, entry: &AuditEntry) -> std::io::Result<()> {
+        if fs::metadata(&self.path).is_ok_and(|metadata| metadata.len() >= MAX_SIZE) {
+            self.rotate()?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        let line = serde_json::to_string(entry)?;
+
+        writeln!(file, "{}", line)
+    }
+
+    fn rotate(&self) -> std::io::Result<()> {
+        for index in (1..ROTATED_FILES).rev() {
+            let from = rotated(&self.path, index);
+
+            if fs::metadata(&from).is_ok() {
+                fs::rename(from, rotated(&self.path, index + 1))?;
+            }
+        }
+
+        fs::rename(&self.path, rotated(&self.path, 1))
+    }
+}
+
+impl Middleware for AuditLog {
+    fn after(
+        &mut self,
+        command: &Command,
+        result: &Result<Outcome, ActionError>,
+        _tasks: &TaskManager,
+    ) {
+        if !command.is_mutating() {
+            return;
+        }
+
+        let entry = AuditEntry {
+            user: self.user.clone(),
+            timestamp: Utc::now(),
+            command: command.name().to_string(),
+            args: command.args(),
+            result: match result {
+                Ok(_) => "ok".to_string(),
+                Err(error) => error.to_string(),
+            },
+        };
+
+        // The action already happened, so a log that can't be written
+        // must not make it fail.
+        let _ = self.append(&entry);
+    }
+}
+
+/// Reads the log and its rotated files, newest entry first. Lines that
+/// can't be read are skipped.
+pub fn read_entries(path: &str) -> Vec<AuditEntry> {
+    let mut files: Vec<String> = (1..=ROTATED_FILES)
+        .rev()
+        .map(|index| rotated(path, index))
+        .collect();
+    files.push(path.to_string());
+
+    let mut entries: Vec<AuditEntry> = files
+        .iter()
+        .filter_map(|file| fs::File::open(file).ok())
+        .flat_map(|file| BufReader::new(file).lines().map_while(Result::ok))
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect();
+
+    entries.reverse();
+    entries
+}
+
+fn rotated(path: &str, index: usize) -> String {
+    format!("{}.{}", path, index)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::{TimeZone, Utc};
+    use tempfile::TempDir;
+    use todo::{
+        command::Command, error::ActionError, middleware::Middleware, outcome::Outcome,
+        task_manager::TaskManager,
+    };
+
+    use super::{read_entries, rotated, AuditEntry, AuditLog, MAX_SIZE, ROTATED_FILES};
+
+    fn entry(command: &str, day: u32) -> AuditEntry {
+        AuditEntry {
+            user: "ana".to_string(),
+            timestamp: Utc.with_ymd_and_hms(2024, 3, day, 10, 0, 0).unwrap(),
+            command: command.to_string(),
+            args: vec!["7".to_string(), "Buy milk".to_string()],
+            result: "ok".to_string(),
+        }
+    }
+
+    fn log_file() -> (TempDir, String) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("audit.log").to_str().unwrap().to_string();
+
+        (dir, path)
+    }
+
+    fn commands(entries: &[AuditEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.command.as_str()).collect()
+    }
+
+    /// Appends copies of an entry until the log is due to be rotated.
+    fn fill(log: &AuditLog, command: &str) {
+        let line = format!("{}\n", serde_json::to_string(&entry(command, 1)).unwrap());
+        let size = fs::metadata(&log.path).map_or(0, |metadata| metadata.len()) as usize;
+        let copies = (MAX_SIZE as usize - size).div_ceil(line.len());
+
+        let mut data = fs::read_to_string(&log.path).unwrap_or_default();
+        data.push_str(&line.repeat(copies));
+        fs::write(&log.path, data).unwrap();
+    }
+
+    #[test]
+    fn test_rotation() {
+        let (dir, path) = log_file();
+        let log = AuditLog::new(&path);
+
+        log.append(&entry("first", 1)).unwrap();
+        assert!(fs::metadata(rotated(&path, 1)).is_err());
+
+        for generation in 0..=ROTATED_FILES {
+            fill(&log, &format!("filler{}", generation));
+            log.append(&entry(&format!("after{}", generation), 2))
+                .unwrap();
+
+            // The entry that rotated the log is the only one left in it.
+            assert_eq!(
+                commands(&read_entries(&path)[..1]),
+                vec![format!("after{}", generation)]
+            );
+            assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+        }
+
+        // The oldest log, with the first entry and the first filler, was
+        // dropped. Newest first, the last filler comes right after the
+        // log's one entry, and the oldest kept log starts the history.
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), ROTATED_FILES + 1);
+
+        let entries = read_entries(&path);
+        assert!(!entries
+            .iter()
+            .any(|entry| entry.command == "first" || entry.command == "filler0"));
+        assert_eq!(entries[1].command, format!("filler{}", ROTATED_FILES));
+        assert_eq!(entries.last().unwrap().command, "after0");
+    }
+
+    #[test]
+    fn test_reads_newest_first() {
+        let (_dir, path) = log_file();
+        let line = |command: &str| serde_json::to_string(&entry(command, 1)).unwrap();
+
+        fs::write(rotated(&path, 2), format!("{}\n{}\n", line("1"), line("2"))).unwrap();
+        fs::write(rotated(&path, 1), format!("{}\nnot json\n", line("3"))).unwrap();
+        fs::write(&path, format!("{}\n{}\n", line("4"), line("5"))).unwrap();
+
+        assert_eq!(
+            commands(&read_entries(&path)),
+            vec!["5", "4", "3", "2", "1"]
+        );
+
+        // Missing files are skipped.
+        fs::remove_file(rotated(&path, 1)).unwrap();
+        assert_eq!(commands(&read_entries(&path)), vec!["5", "4", "2", "1"]);
+    }
+
+    #[test]
+    fn test_records_mutating_commands() {
+        let (_dir, path) = log_file();
+        let mut log = AuditLog::new(&path);
+        let tasks = TaskManager::new();
+
+        log.after(&Command::Display, &Ok(Outcome::Done), &tasks);
+        log.after(&Command::Stats, &Ok(Outcome::Done), &tasks);
+        assert!(fs::metadata(&path).is_err());
+
+        log.after(
+            &Command::Remove { id: 3 },
+            &Err(ActionError::NotFound(3)),
+            &tasks,
+        );
+        log.after(&Command::Complete { id: 4 }, &Ok(Outcome::Done), &tasks);
+
+        let entries = read_entries(&path);
+        assert_eq!(commands(&entries), vec!["complete", "remove"]);
+        assert_eq!(entries[0].args, vec!["4"]);
+        assert_eq!(entries[0].result, "ok");
+        assert_eq!(entries[1].result, ActionError::NotFound(3).to_string());
+    }
+
+    #[test]
+    fn test_filters() {
+        let entry = entry("update", 5);
+
+        for filter in [
+            "",
+            "command:update",
+            "COMMAND:Update",
+            "user:ana",
+            "result:ok",
+            "date:2024-03",
+            "date:2024-03-05",
+            "milk",
+            "7",
+            "command:update user:ana date:2024-03-05 milk",
+        ] {
+            assert!(entry.matches(filter), "'{}' should match", filter);
+        }
+
+        for filter in [
+            "command:remove",
+            "command:upd",
+            "user:an",
+            "result:failed",
+            "date:2024-04",
+            "bread",
+            "command:update bread",
+        ] {
+            assert!(!entry.matches(filter), "'{}' shouldn't match", filter);
+        }
+    }
+}
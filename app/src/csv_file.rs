@@ -0,0 +1,489 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use csv::{ReaderBuilder, StringRecord, Trim, WriterBuilder};
+use serde_json::{Map, Value};
+use todo::task::Task;
+
+use crate::transfer::{Imported, RowError};
+
+/// Columns written by `export`, and read in this order from a file
+/// without a header.
+const FIELDS: [&str; 11] = [
+    "id",
+    "name",
+    "description",
+    "done",
+    "position",
+    "created_at",
+    "completed_at",
+    "priority",
+    "projects",
+    "contexts",
+    "attributes",
+];
+
+pub fn export(tasks: &[Task]) -> Result<String, String> {
+    let mut writer = WriterBuilder::new().from_writer(Vec::new());
+
+    let rows = std::iter::once(FIELDS.map(String::from)).chain(tasks.iter().map(|task| {
+        [
+            task.id.to_string(),
+            task.name.clone(),
+            task.description.clone(),
+            task.done.to_string(),
+            task.position.to_string(),
+            task.created_at
+                .map(|at| at.to_rfc3339())
+                .unwrap_or_default(),
+            task.completed_at
+                .map(|at| at.to_rfc3339())
+                .unwrap_or_default(),
+            task.priority.map(String::from).unwrap_or_default(),
+            task.projects.join(" "),
+            task.contexts.join(" "),
+            attributes(task),
+        ]
+    }));
+
+    for row in rows {
+        writer
+            .write_record(&row)
+            .map_err(|error| error.to_string())?;
+    }
+
+    let data = writer.into_inner().map_err(|error| error.to_string())?;
+
+    String::from_utf8(data).map_err(|error| error.to_string())
+}
+
+/// Reads tasks from CSV. `columns` maps columns to fields as a comma
+/// separated list of `column=field`, where a column is a header name or a
+/// number starting at 1, for example `Title=name, 3=description`. The
+/// first row is taken as a header when a column is mapped by name or it
+/// looks like one: see `is_header`. Ids and positions in the file are
+/// ignored.
+pub fn import(data: &str, columns: &str) -> Result<Imported, String> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(Trim::All)
+        .from_reader(data.as_bytes());
+
+    let mut imported = Imported::default();
+    let mut records = Vec::new();
+
+    for record in reader.records() {
+        match record {
+            Ok(record) => records.push(record),
+            Err(error) => imported.errors.push(RowError {
+                line: error.position().map(|at| at.line()).unwrap_or(0),
+                reason: error.to_string(),
+            }),
+        }
+    }
+
+    let mapping = parse_mapping(columns)?;
+
+    let header = match records.first() {
+        Some(first) if is_header(first, &mapping) => Some(records.remove(0)),
+        _ => None,
+    };
+
+    let fields = fields_of_columns(header.as_ref(), &mapping)?;
+
+    for record in &records {
+        match read_task(record, &fields) {
+            Ok(task) => imported.tasks.push(task),
+            Err(reason) => imported.errors.push(RowError {
+                line: record.position().map(|at| at.line()).unwrap_or(0),
+                reason,
+            }),
+        }
+    }
+
+    Ok(imported)
+}
+
+/// A column as the user named it: by header or by number.
+enum Column {
+    Named(String),
+    Number(usize),
+}
+
+fn parse_mapping(columns: &str) -> Result<Vec<(Column, &'static str)>, String> {
+    columns
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (column, field) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("'{}' should be column=field", entry))?;
+
+            let field = known_field(field.trim())
+                .ok_or_else(|| format!("'{}' is not a task field", field.trim()))?;
+
+            let column = match column.trim().parse::<usize>() {
+                Ok(0) => return Err("columns are numbered from 1".to_string()),
+                Ok(number) => Column::Number(number),
+                Err(_) => Column::Named(column.trim().to_lowercase()),
+            };
+
+            Ok((column, field))
+        })
+        .collect()
+}
+
+fn known_field(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+
+    FIELDS.into_iter().find(|field| *field == name)
+}
+
+/// A row is a header when a column is mapped by name. Otherwise most of
+/// its cells must name a field, `name` among them unless a column is
+/// mapped to it, so a first task that happens to be called `Done` isn't
+/// taken for one.
+fn is_header(record: &StringRecord, mapping: &[(Column, &'static str)]) -> bool {
+    if mapping
+        .iter()
+        .any(|(column, _)| matches!(column, Column::Named(_)))
+    {
+        return true;
+    }
+
+    let cells: Vec<&str> = record.iter().filter(|cell| !cell.is_empty()).collect();
+    let fields: Vec<&str> = cells.iter().filter_map(|cell| known_field(cell)).collect();
+
+    let names_task = fields.contains(&"name") || mapping.iter().any(|(_, field)| *field == "name");
+
+    fields.len() * 2 > cells.len() && names_task
+}
+
+/// The field each column holds, by column index.
+fn fields_of_columns(
+    header: Option<&StringRecord>,
+    mapping: &[(Column, &'static str)],
+) -> Result<Vec<Option<&'static str>>, String> {
+    let mut fields: Vec<Option<&'static str>> = match header {
+        Some(header) => header.iter().map(known_field).collect(),
+        None if mapping.is_empty() => FIELDS.into_iter().map(Some).collect(),
+        None => Vec::new(),
+    };
+
+    for (column, field) in mapping {
+        let index = match column {
+            Column::Number(number) => number - 1,
+            Column::Named(name) => header
+                .and_then(|header| header.iter().position(|cell| cell.to_lowercase() == *name))
+                .ok_or_else(|| format!("there is no column named '{}'", name))?,
+        };
+
+        if fields.len() <= index {
+            fields.resize(index + 1, None);
+        }
+
+        // A field comes from one column only.
+        for other in fields.iter_mut() {
+            if *other == Some(*field) {
+                *other = None;
+            }
+        }
+
+        fields[index] = Some(field);
+    }
+
+    if !fields.contains(&Some("name")) {
+        return Err("no column holds the task name".to_string());
+    }
+
+    Ok(fields)
+}
+
+fn read_task(record: &StringRecord, fields: &[Option<&'static str>]) -> Result<Task, String> {
+    let value = |wanted: &str| -> &str {
+        fields
+            .iter()
+            .position(|field| *field == Some(wanted))
+            .and_then(|index| record.get(index))
+            .unwrap_or("")
+    };
+
+    let name = value("name");
+    let description = value("description");
+
+    if name.is_empty() {
+        return Err("the name is empty".to_string());
+    }
+
+    if description.is_empty() {
+        return Err("the description is empty".to_string());
+    }
+
+    let done = match value("done").to_lowercase().as_str() {
+        "" | "false" | "no" | "0" => false,
+        "true" | "yes" | "1" | "x" => true,
+        other => return Err(format!("'{}' is not true or false", other)),
+    };
+
+    let priority = match value("priority") {
+        "" => None,
+        priority => match priority.chars().next() {
+            Some(letter) if priority.len() == 1 && letter.is_ascii_uppercase() => Some(letter),
+            _ => return Err(format!("'{}' is not a priority from A to Z", priority)),
+        },
+    };
+
+    let attributes = read_attributes(value("attributes"))?;
+
+    Ok(Task {
+        id: 0,
+        name: name.to_string(),
+        description: description.to_string(),
+        done,
+        position: 0,
+        created_at: read_time(value("created_at"))?,
+        completed_at: read_time(value("completed_at"))?,
+        priority,
+        projects: words(value("projects")),
+        contexts: words(value("contexts")),
+        attributes,
+    })
+}
+
+/// The attributes as a JSON object, as their values may hold anything,
+/// such as the raw lines kept from an iCalendar file. Empty when there
+/// are none.
+fn attributes(task: &Task) -> String {
+    if task.attributes.is_empty() {
+        return String::new();
+    }
+
+    let object: Map<String, Value> = task
+        .attributes
+        .iter()
+        .map(|(key, value)| (key.clone(), Value::from(value.as_str())))
+        .collect();
+
+    Value::Object(object).to_string()
+}
+
+/// Reads the JSON object `export` writes or, in a file written by hand,
+/// `key:value` words separated by spaces.
+fn read_attributes(value: &str) -> Result<BTreeMap<String, String>, String> {
+    if value.starts_with('{') {
+        return serde_json::from_str(value)
+            .map_err(|error| format!("the attributes are not a JSON object of texts: {}", error));
+    }
+
+    value
+        .split_whitespace()
+        .map(|pair| match pair.split_once(':') {
+            Some((key, value)) if !key.is_empty() && !value.is_empty() => {
+                Ok((key.to_string(), value.to_string()))
+            }
+            _ => Err(format!("'{}' should be key:value", pair)),
+        })
+        .collect()
+}
+
+/// Tags are separated by spaces, and may keep their `+` or `@`.
+fn words(value: &str) -> Vec<String> {
+    value
+        .split_whitespace()
+        .map(|word| word.trim_start_matches(['+', '@']).to_string())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+fn read_time(value: &str) -> Result<Option<DateTime<Utc>>, String> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    DateTime::parse_from_rfc3339(value)
+        .map(|at| Some(at.with_timezone(&Utc)))
+        .map_err(|_| format!("'{}' is not an RFC 3339 time", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use todo::task::Task;
+
+    use super::{export, import};
+    use crate::ical_file;
+
+    fn task(name: &str, description: &str) -> Task {
+        Task {
+            name: name.to_string(),
+            description: description.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn names(data: &str, columns: &str) -> Vec<String> {
+        let imported = import(data, columns).unwrap();
+        assert!(imported.errors.is_empty(), "{:?}", imported.errors);
+
+        imported.tasks.into_iter().map(|task| task.name).collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut done = task("Write, then \"send\"", "A letter\nover two lines");
+        done.done = true;
+        done.created_at = Some(Utc.with_ymd_and_hms(2024, 3, 1, 8, 0, 0).unwrap());
+        done.completed_at = Some(Utc.with_ymd_and_hms(2024, 3, 2, 18, 30, 0).unwrap());
+        done.priority = Some('C');
+        done.projects = vec!["home".to_string(), "post".to_string()];
+        done.contexts = vec!["desk".to_string()];
+        done.attributes
+            .insert("note".to_string(), "a: b, \"c\" d".to_string());
+        let open = task("Read", "Read");
+
+        let data = export(&[done.clone(), open.clone()]).unwrap();
+        assert!(data.starts_with("id,name,description,done,"));
+
+        let imported = import(&data, "").unwrap();
+        assert!(imported.errors.is_empty());
+        assert_eq!(imported.tasks, vec![done, open]);
+    }
+
+    #[test]
+    fn test_header() {
+        // Found by most cells naming fields, in any order and case.
+        let data = "Description,Name,Other\nAbout it,First,ignored\n";
+        let imported = import(data, "").unwrap();
+        assert_eq!(imported.tasks[0].name, "First");
+        assert_eq!(imported.tasks[0].description, "About it");
+
+        // Without one, columns are read in the order `export` writes them.
+        assert_eq!(
+            names("1,First,About it\n2,Second,About that\n", ""),
+            vec!["First", "Second"]
+        );
+    }
+
+    #[test]
+    fn test_headerless_task_named_like_a_field() {
+        assert_eq!(
+            names("1,Done,About it\n2,Name,About that\n", ""),
+            vec!["Done", "Name"]
+        );
+        assert_eq!(
+            names("Done,Finish it\n", "1=name,2=description"),
+            vec!["Done"]
+        );
+    }
+
+    #[test]
+    fn test_mapping() {
+        let data = "Title,Notes\nFirst,About it\n";
+        assert_eq!(names(data, "Title=name, notes=description"), vec!["First"]);
+
+        let data = "About it,First\n";
+        assert_eq!(names(data, "2=name,1=description"), vec!["First"]);
+
+        // A mapped field is no longer read from its own column.
+        let data = "name,description,title\nOld,About it,New\n";
+        assert_eq!(names(data, "title=name"), vec!["New"]);
+    }
+
+    #[test]
+    fn test_mapping_errors() {
+        for (columns, error) in [
+            ("name", "'name' should be column=field"),
+            ("1=title", "'title' is not a task field"),
+            ("0=name", "columns are numbered from 1"),
+            ("Missing=name", "there is no column named 'missing'"),
+            ("1=description", "no column holds the task name"),
+        ] {
+            assert_eq!(import("First,About it\n", columns).unwrap_err(), error);
+        }
+    }
+
+    #[test]
+    fn test_row_errors() {
+        let data = "name,description,done,created_at,priority\n\
+                    Good,Good,,,\n\
+                    ,No name,,,\n\
+                    No description,,,,\n\
+                    Bad done,Bad done,maybe,,\n\
+                    Bad time,Bad time,,yesterday,\n\
+                    Bad priority,Bad priority,,,AB\n\
+                    Done,Done,x,,\n";
+
+        let imported = import(data, "").unwrap();
+        let names: Vec<&str> = imported
+            .tasks
+            .iter()
+            .map(|task| task.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Good", "Done"]);
+        assert!(imported.tasks[1].done);
+
+        let errors: Vec<String> = imported.errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "line 3: the name is empty",
+                "line 4: the description is empty",
+                "line 5: 'maybe' is not true or false",
+                "line 6: 'yesterday' is not an RFC 3339 time",
+                "line 7: 'AB' is not a priority from A to Z",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_attributes() {
+        let data = "name,description,attributes\n\
+                    Words,Words,due:2024-05-01 size:L\n\
+                    Object,Object,\"{\"\"due\"\": \"\"2024-05-01\"\"}\"\n\
+                    Bad word,Bad word,due:\n\
+                    Bad object,Bad object,{\"\"due\"\": 1}\n";
+
+        let imported = import(data, "").unwrap();
+
+        assert_eq!(imported.tasks[0].attributes.len(), 2);
+        assert_eq!(imported.tasks[0].attributes["size"], "L");
+        assert_eq!(imported.tasks[1].attributes["due"], "2024-05-01");
+
+        let lines: Vec<u64> = imported.errors.iter().map(|error| error.line).collect();
+        assert_eq!(lines, vec![4, 5]);
+        assert_eq!(imported.errors[0].reason, "'due:' should be key:value");
+    }
+
+    #[test]
+    fn test_round_trip_from_ical() {
+        let calendar = "BEGIN:VCALENDAR\r\n\
+                        BEGIN:VTODO\r\n\
+                        UID:abc@example.com\r\n\
+                        SUMMARY:Meet\r\n\
+                        LOCATION:Office\\, 2nd floor\r\n\
+                        DUE;VALUE=DATE:20240501\r\n\
+                        BEGIN:VALARM\r\n\
+                        ACTION:DISPLAY\r\n\
+                        TRIGGER:-PT15M\r\n\
+                        END:VALARM\r\n\
+                        END:VTODO\r\n\
+                        END:VCALENDAR\r\n";
+
+        let tasks = ical_file::import(calendar).tasks;
+        assert_eq!(tasks[0].attributes.len(), 4);
+
+        let data = export(&tasks).unwrap();
+        let imported = import(&data, "").unwrap();
+
+        assert!(imported.errors.is_empty(), "{:?}", imported.errors);
+        assert_eq!(imported.tasks, tasks);
+        assert_eq!(export(&imported.tasks).unwrap(), data);
+
+        let exported = ical_file::export(&imported.tasks);
+        assert!(exported.contains("\r\nUID:abc@example.com\r\n"));
+        assert!(exported.contains("\r\nLOCATION:Office\\, 2nd floor\r\n"));
+        assert!(exported.contains("\r\nBEGIN:VALARM\r\nACTION:DISPLAY\r\n"));
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use todo::task::Task;
+
+use crate::task_mapper::TaskMapper;
+
+/// Version of the document `save` writes. Bump it, and add a step to
+/// `MIGRATIONS`, whenever a change would confuse an older reader.
+pub const FORMAT_VERSION: u64 = 2;
+
+/// `MIGRATIONS[n]` turns a version `n + 1` document into version `n + 2`.
+/// Version 1 is the bare array of tasks written before documents had a
+/// version.
+const MIGRATIONS: [fn(Value) -> Value; (FORMAT_VERSION - 1) as usize] = [wrap_tasks];
+
+/// How `tasks.json` is laid out from version 2 on.
+#[derive(Serialize, Deserialize)]
+pub struct Document<'a> {
+    pub version: u64,
+    #[serde(default)]
+    pub metadata: Metadata,
+    pub tasks: Vec<TaskMapper<'a>>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    pub saved_at: Option<DateTime<Utc>>,
+    /// Version of the app that saved the document.
+    pub saved_by: String,
+}
+
+#[derive(Debug)]
+pub enum DocumentError {
+    Invalid(String),
+    /// Written by a newer version of the app, in a format this one can't
+    /// know how to read.
+    Newer(u64),
+}
+
+impl Display for DocumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocumentError::Invalid(reason) => write!(f, "{}", reason),
+            DocumentError::Newer(version) => write!(
+                f,
+                "the file has format version {}, but this version of the app reads up to {}",
+                version, FORMAT_VERSION
+            ),
+        }
+    }
+}
+
+impl From<serde_json::Error> for DocumentError {
+    fn from(error: serde_json::Error) -> Self {
+        DocumentError::Invalid(error.to_string())
+    }
+}
+
+impl<'a> Document<'a> {
+    pub fn new(tasks: Vec<&'a Task>) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            metadata: Metadata {
+                saved_at: Some(Utc::now()),
+                saved_by: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            tasks: tasks.into_iter().map(TaskMapper::from).collect(),
+        }
+    }
+}
+
+/// Reads the tasks of a document of any version up to `FORMAT_VERSION`,
+/// upgrading it first. An empty file holds no tasks.
+pub fn read(data: &str) -> Result<Vec<Task>, DocumentError> {
+    if data.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut value: Value = serde_json::from_str(data)?;
+    let version = version_of(&value)?;
+
+    if version > FORMAT_VERSION {
+        return Err(DocumentError::Newer(version));
+    }
+
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        value = migration(value);
+    }
+
+    let document: Document = serde_json::from_value(value)?;
+
+    Ok(document.tasks.into_iter().map(Task::from).collect())
+}
+
+fn version_of(value: &Value) -> Result<u64, DocumentError> {
+    match value {
+        Value::Array(_) => Ok(1),
+        Value::Object(document) => match document.get("version").and_then(Value::as_u64) {
+            Some(version) if version >= 1 => Ok(version),
+            _ => Err(DocumentError::Invalid(
+                "the document has no valid version".to_string(),
+            )),
+        },
+        _ => Err(DocumentError::Invalid(
+            "the document is neither a list nor an object".to_string(),
+        )),
+    }
+}
+
+/// 1 to 2: the list goes inside a document.
+fn wrap_tasks(tasks: Value) -> Value {
+    json!({ "version": 2, "metadata": {}, "tasks": tasks })
+}
+
+#[cfg(test)]
+mod tests {
+    use todo::task::Task;
+
+    use super::{read, Document, DocumentError, FORMAT_VERSION};
+
+    #[test]
+    fn test_reads_bare_array() {
+        let tasks = read(
+            r#"[
+                {"id": 1, "name": "First", "description": "About it", "done": false},
+                {"id": 2, "name": "Second", "description": "Second", "done": true, "position": 5}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].name, "First");
+        assert_eq!(tasks[0].description, "About it");
+        assert_eq!(tasks[0].position, 0);
+        assert!(tasks[1].done);
+        assert_eq!(tasks[1].position, 5);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let task = Task {
+            id: 4,
+            name: "Plan".to_string(),
+            description: "Plan the week".to_string(),
+            position: 1024,
+            priority: Some('A'),
+            projects: vec!["work".to_string()],
+            contexts: vec!["desk".to_string()],
+            attributes: [("due".to_string(), "2024-05-01".to_string())].into(),
+            ..Default::default()
+        };
+
+        let data = serde_json::to_string(&Document::new(vec![&task])).unwrap();
+
+        assert!(data.contains(&format!("\"version\":{}", FORMAT_VERSION)));
+        assert_eq!(read(&data).unwrap(), vec![task]);
+
+        // Metadata is optional.
+        let tasks = read(r#"{"version": 2, "tasks": []}"#).unwrap();
+        assert!(tasks.is_empty());
+    }
+
+    #[test]
+    fn test_empty_file() {
+        assert!(read("").unwrap().is_empty());
+        assert!(read(" \n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_refuses_newer_version() {
+        let data = format!(r#"{{"version": {}, "tasks": []}}"#, FORMAT_VERSION + 1);
+
+        match read(&data) {
+            Err(DocumentError::Newer(version)) => assert_eq!(version, FORMAT_VERSION + 1),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_invalid_documents() {
+        for data in [
+            "{",
+            "42",
+            r#"{"tasks": []}"#,
+            r#"{"version": 0, "tasks": []}"#,
+            r#"{"version": "2", "tasks": []}"#,
+            r#"{"version": 2}"#,
+            r#"[{"id": 1}]"#,
+        ] {
+            assert!(
+                matches!(read(data), Err(DocumentError::Invalid(_))),
+                "{} should be invalid",
+                data
+            );
+        }
+    }
+}
@@ -0,0 +1,545 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use todo::task::Task;
+
+use crate::transfer::{Imported, RowError};
+
+/// Prefix of the attributes that keep the properties and components of a
+/// VTODO this app doesn't know, so they are written back on export.
+const KEPT: &str = "ical.";
+
+/// Property holding the other `key:value` attributes, as `key:value`.
+const ATTRIBUTE: &str = "X-TODO-ATTRIBUTE";
+
+/// Longest line, in bytes, before it is folded.
+const LINE_LENGTH: usize = 75;
+
+/// Writes the tasks as a calendar of VTODO components. Priorities `A` to
+/// `I` become 1 to 9, projects and `@contexts` become categories and the
+/// `due` attribute, when it is a date, becomes the due date.
+pub fn export(tasks: &[Task]) -> String {
+    let stamp = time(Utc::now());
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:-//todo//app {}//EN", env!("CARGO_PKG_VERSION")),
+    ];
+
+    for task in tasks {
+        lines.push("BEGIN:VTODO".to_string());
+
+        if !task.attributes.contains_key(&format!("{}UID", KEPT)) {
+            lines.push(format!("UID:task-{}", task.id));
+        }
+
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("SUMMARY:{}", escape(&task.name)));
+
+        if task.description != task.name {
+            lines.push(format!("DESCRIPTION:{}", escape(&task.description)));
+        }
+
+        lines.push(format!(
+            "STATUS:{}",
+            if task.done {
+                "COMPLETED"
+            } else {
+                "NEEDS-ACTION"
+            }
+        ));
+
+        lines.extend(task.created_at.map(|at| format!("CREATED:{}", time(at))));
+        lines.extend(
+            task.completed_at
+                .map(|at| format!("COMPLETED:{}", time(at))),
+        );
+
+        if let Some(priority @ 'A'..='Z') = task.priority {
+            let level = (priority as u8 - b'A' + 1).min(9);
+            lines.push(format!("PRIORITY:{}", level));
+        }
+
+        let categories: Vec<String> = task
+            .projects
+            .iter()
+            .map(|project| escape(project))
+            .chain(
+                task.contexts
+                    .iter()
+                    .map(|context| escape(&format!("@{}", context))),
+            )
+            .collect();
+
+        if !categories.is_empty() {
+            lines.push(format!("CATEGORIES:{}", categories.join(",")));
+        }
+
+        for (key, value) in &task.attributes {
+            if key.starts_with(KEPT) {
+                lines.extend(value.lines().map(String::from));
+            } else if let Some(due) = (key == "due").then(|| read_day(value)).flatten() {
+                lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+            } else {
+                lines.push(format!(
+                    "{}:{}",
+                    ATTRIBUTE,
+                    escape(&format!("{}:{}", key, value))
+                ));
+            }
+        }
+
+        lines.push("END:VTODO".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// Reads every VTODO in `data`, whichever calendar it is in. Properties
+/// and components the app has no field for are kept as attributes and
+/// written back on export. A VTODO without a summary, or with a date that
+/// can't be read, is a row error.
+pub fn import(data: &str) -> Imported {
+    let mut imported = Imported::default();
+    // The VTODO being read: the line it starts at and its lines.
+    let mut current: Option<(u64, Vec<String>)> = None;
+
+    for (line, content) in unfold(data) {
+        let is_begin = content.eq_ignore_ascii_case("BEGIN:VTODO");
+
+        match &mut current {
+            None if is_begin => current = Some((line, Vec::new())),
+            None => {}
+            Some(_) if content.eq_ignore_ascii_case("END:VTODO") => {
+                let (start, lines) = current.take().unwrap();
+
+                match read_task(&lines) {
+                    Ok(task) => imported.tasks.push(task),
+                    Err(reason) => imported.errors.push(RowError {
+                        line: start,
+                        reason,
+                    }),
+                }
+            }
+            Some((start, _)) if is_begin => {
+                imported.errors.push(RowError {
+                    line: *start,
+                    reason: "the VTODO has no END".to_string(),
+                });
+                current = Some((line, Vec::new()));
+            }
+            Some((_, lines)) => lines.push(content),
+        }
+    }
+
+    if let Some((line, _)) = current {
+        imported.errors.push(RowError {
+            line,
+            reason: "the VTODO has no END".to_string(),
+        });
+    }
+
+    imported
+}
+
+fn read_task(lines: &[String]) -> Result<Task, String> {
+    let mut task = Task::default();
+
+    let mut lines = lines.iter();
+
+    while let Some(line) = lines.next() {
+        let (name, value) = split(line)?;
+
+        match name.as_str() {
+            "SUMMARY" => task.name = unescape(value).trim().to_string(),
+            "DESCRIPTION" => task.description = unescape(value).trim().to_string(),
+            "STATUS" => task.done = value.eq_ignore_ascii_case("COMPLETED"),
+            "CREATED" => task.created_at = Some(read_time(value)?),
+            "COMPLETED" => task.completed_at = Some(read_time(value)?),
+            "DUE" => {
+                let due = read_time(value)?.date_naive();
+                task.attributes
+                    .insert("due".to_string(), due.format("%Y-%m-%d").to_string());
+            }
+            "PRIORITY" => {
+                task.priority = match value.trim().parse::<u8>() {
+                    Ok(0) => None,
+                    Ok(level @ 1..=9) => Some((b'A' + level - 1) as char),
+                    _ => return Err(format!("'{}' is not a priority from 0 to 9", value)),
+                }
+            }
+            "CATEGORIES" => {
+                for category in split_list(value) {
+                    match category.strip_prefix('@') {
+                        Some(context) => task.contexts.push(context.to_string()),
+                        None => task.projects.push(category),
+                    }
+                }
+            }
+            ATTRIBUTE => {
+                let attribute = unescape(value);
+
+                match attribute.split_once(':') {
+                    Some((key, value)) if !key.is_empty() => {
+                        task.attributes.insert(key.to_string(), value.to_string());
+                    }
+                    _ => keep(&mut task.attributes, &name, line.clone()),
+                }
+            }
+            // Written again on export.
+            "DTSTAMP" => {}
+            "BEGIN" => {
+                let component = value.to_uppercase();
+                let mut kept = vec![line.clone()];
+
+                for line in lines.by_ref() {
+                    kept.push(line.clone());
+
+                    if line.eq_ignore_ascii_case(&format!("END:{}", component)) {
+                        break;
+                    }
+                }
+
+                keep(&mut task.attributes, &component, kept.join("\n"));
+            }
+            _ => keep(&mut task.attributes, &name, line.clone()),
+        }
+    }
+
+    if task.name.is_empty() {
+        return Err("the summary is empty".to_string());
+    }
+
+    if task.description.is_empty() {
+        task.description = task.name.clone();
+    }
+
+    Ok(task)
+}
+
+/// Keeps the raw lines of an unknown property or component, numbering
+/// the ones that appear more than once.
+fn keep(attributes: &mut BTreeMap<String, String>, name: &str, lines: String) {
+    let mut key = format!("{}{}", KEPT, name);
+    let mut count = 1;
+
+    while attributes.contains_key(&key) {
+        count += 1;
+        key = format!("{}{}.{}", KEPT, name, count);
+    }
+
+    attributes.insert(key, lines);
+}
+
+/// Joins folded lines, keeping the line number each starts at.
+fn unfold(data: &str) -> Vec<(u64, String)> {
+    let mut lines: Vec<(u64, String)> = Vec::new();
+
+    for (index, line) in data.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some((_, previous))) => previous.push_str(rest),
+            _ if line.is_empty() => {}
+            _ => lines.push((index as u64 + 1, line.to_string())),
+        }
+    }
+
+    lines
+}
+
+/// Splits a content line into its upper case name and its value, leaving
+/// out the parameters. Colons inside quoted parameters don't end them.
+fn split(line: &str) -> Result<(String, &str), String> {
+    let mut quoted = false;
+
+    for (index, character) in line.char_indices() {
+        match character {
+            '"' => quoted = !quoted,
+            ':' if !quoted => {
+                let name = line[..index].split(';').next().unwrap_or_default();
+
+                return Ok((name.to_uppercase(), &line[index + 1..]));
+            }
+            _ => {}
+        }
+    }
+
+    Err(format!("'{}' has no value", line))
+}
+
+/// Splits a list of text values on the commas that aren't escaped.
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut characters = value.chars();
+
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => {
+                item.push(character);
+                item.extend(characters.next());
+            }
+            ',' => items.push(std::mem::take(&mut item)),
+            _ => item.push(character),
+        }
+    }
+
+    items.push(item);
+
+    items
+        .iter()
+        .map(|item| unescape(item).trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut characters = text.chars();
+
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            unescaped.push(character);
+            continue;
+        }
+
+        match characters.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+/// Splits a line longer than `LINE_LENGTH` bytes into lines that start
+/// with a space, without cutting a character in two.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut length = 0;
+
+    for character in line.chars() {
+        if length + character.len_utf8() > LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+
+        folded.push(character);
+        length += character.len_utf8();
+    }
+
+    folded.push_str("\r\n");
+    folded
+}
+
+fn time(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Reads a DATE-TIME or a DATE, which is midnight. Times in a time zone
+/// other than UTC are read as if they were in UTC.
+fn read_time(value: &str) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    let local = value.trim_end_matches('Z');
+
+    NaiveDateTime::parse_from_str(local, "%Y%m%dT%H%M%S")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(local, "%Y%m%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .map(|time| time.and_utc())
+        .ok_or_else(|| format!("'{}' is not an iCalendar date", value))
+}
+
+fn read_day(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use todo::task::Task;
+
+    use super::{export, import, LINE_LENGTH};
+
+    fn calendar(todos: &[&str]) -> String {
+        let mut lines = vec!["BEGIN:VCALENDAR".to_string()];
+
+        for todo in todos {
+            lines.push("BEGIN:VTODO".to_string());
+            lines.extend(todo.lines().map(|line| line.trim().to_string()));
+            lines.push("END:VTODO".to_string());
+        }
+
+        lines.push("END:VCALENDAR".to_string());
+        lines.join("\r\n")
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let task = Task {
+            id: 3,
+            name: "Plan; then, act \\ rest".to_string(),
+            description: "First line\nsecond line".to_string(),
+            done: true,
+            created_at: Some(Utc.with_ymd_and_hms(2024, 1, 2, 9, 30, 0).unwrap()),
+            completed_at: Some(Utc.with_ymd_and_hms(2024, 1, 3, 17, 0, 5).unwrap()),
+            priority: Some('B'),
+            projects: vec!["home, garden".to_string()],
+            contexts: vec!["phone".to_string()],
+            attributes: [
+                ("due".to_string(), "2024-02-01".to_string()),
+                ("size".to_string(), "L: large".to_string()),
+            ]
+            .into(),
+            ..Default::default()
+        };
+
+        let data = export(std::slice::from_ref(&task));
+        let imported = import(&data);
+
+        // Ids are given on import; the UID is kept to be written again.
+        let mut expected = Task { id: 0, ..task };
+        expected
+            .attributes
+            .insert("ical.UID".to_string(), "UID:task-3".to_string());
+
+        assert!(imported.errors.is_empty(), "{:?}", imported.errors);
+        assert_eq!(imported.tasks, vec![expected]);
+        assert!(data.contains("\r\nPRIORITY:2\r\n"));
+        assert!(data.contains("\r\nCATEGORIES:home\\, garden,@phone\r\n"));
+        assert!(data.contains("\r\nDUE;VALUE=DATE:20240201\r\n"));
+    }
+
+    #[test]
+    fn test_folding() {
+        let name = "é".repeat(100);
+        let task = Task {
+            id: 1,
+            name: name.clone(),
+            description: name.clone(),
+            ..Default::default()
+        };
+
+        let data = export(&[task]);
+
+        assert!(data.split("\r\n").all(|line| line.len() <= LINE_LENGTH));
+        assert!(data.contains("\r\n é"));
+        assert_eq!(import(&data).tasks[0].name, name);
+
+        // Folded with a tab, or with bare newlines, reads the same.
+        let imported = import("BEGIN:VTODO\nSUMMARY:Long\n\t name\nEND:VTODO\n");
+        assert_eq!(imported.tasks[0].name, "Long name");
+    }
+
+    #[test]
+    fn test_priorities() {
+        let priority = |value: &str| {
+            let imported = import(&calendar(&[&format!("SUMMARY:Task\nPRIORITY:{}", value)]));
+            imported
+                .tasks
+                .first()
+                .map(|task| task.priority)
+                .ok_or_else(|| imported.errors[0].reason.clone())
+        };
+
+        assert_eq!(priority("0"), Ok(None));
+        assert_eq!(priority("1"), Ok(Some('A')));
+        assert_eq!(priority(" 9"), Ok(Some('I')));
+        assert_eq!(
+            priority("10"),
+            Err("'10' is not a priority from 0 to 9".to_string())
+        );
+
+        let mut task = import(&calendar(&["SUMMARY:Task"])).tasks.remove(0);
+        task.priority = Some('Q');
+        assert!(export(&[task]).contains("\r\nPRIORITY:9\r\n"));
+    }
+
+    #[test]
+    fn test_keeps_unknown_properties() {
+        let imported = import(&calendar(&["UID:abc\n\
+             SUMMARY;LANGUAGE=en:Meet\n\
+             ATTENDEE;CN=\"Doe: Jane\":mailto:jane@example.com\n\
+             ATTENDEE:mailto:joe@example.com\n\
+             X-TODO-ATTRIBUTE:size:L\n\
+             BEGIN:VALARM\n\
+             TRIGGER:-PT15M\n\
+             END:VALARM"]));
+        let task = &imported.tasks[0];
+
+        assert_eq!(task.name, "Meet");
+        assert_eq!(task.attributes["size"], "L");
+        assert_eq!(task.attributes["ical.UID"], "UID:abc");
+        assert_eq!(
+            task.attributes["ical.ATTENDEE"],
+            "ATTENDEE;CN=\"Doe: Jane\":mailto:jane@example.com"
+        );
+        assert_eq!(
+            task.attributes["ical.ATTENDEE.2"],
+            "ATTENDEE:mailto:joe@example.com"
+        );
+        assert_eq!(
+            task.attributes["ical.VALARM"],
+            "BEGIN:VALARM\nTRIGGER:-PT15M\nEND:VALARM"
+        );
+
+        let data = export(&imported.tasks);
+        assert!(data.contains("\r\nUID:abc\r\n"));
+        assert!(!data.contains("UID:task-"));
+        assert!(data.contains("\r\nBEGIN:VALARM\r\nTRIGGER:-PT15M\r\nEND:VALARM\r\n"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let data = calendar(&[
+            "SUMMARY:First",
+            "DESCRIPTION:No summary",
+            "SUMMARY:Bad date\nCREATED:yesterday",
+            "SUMMARY:No value\nLOCATION",
+            "SUMMARY:Last",
+        ]);
+        let data = format!("{}\r\nBEGIN:VTODO\r\nSUMMARY:Cut short\r\n", data);
+
+        let imported = import(&data);
+        let names: Vec<&str> = imported
+            .tasks
+            .iter()
+            .map(|task| task.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["First", "Last"]);
+
+        let errors: Vec<String> = imported.errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "line 5: the summary is empty",
+                "line 8: 'yesterday' is not an iCalendar date",
+                "line 12: 'LOCATION' has no value",
+                "line 20: the VTODO has no END",
+            ]
+        );
+
+        // A VTODO that starts before the last one ended.
+        let imported = import("BEGIN:VTODO\nSUMMARY:Open\nBEGIN:VTODO\nSUMMARY:Next\nEND:VTODO\n");
+        assert_eq!(imported.tasks[0].name, "Next");
+        assert_eq!(
+            imported.errors[0].to_string(),
+            "line 1: the VTODO has no END"
+        );
+    }
+}
@@ -0,0 +1,509 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, ErrorKind, Write},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use todo::{error::LoadError, repository::Repository, task::Task};
+
+use crate::task_mapper::TaskMapper;
+
+/// Changes written after the last snapshot before a new one is taken.
+const SNAPSHOT_EVERY: usize = 100;
+
+/// Snapshots kept when the journal is compacted. Everything before the
+/// oldest one is dropped, so the history reaches back that far.
+const KEPT_SNAPSHOTS: usize = 10;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Event<'a> {
+    Put {
+        task: TaskMapper<'a>,
+    },
+    Delete {
+        id: u32,
+    },
+    /// The whole list, so loading doesn't replay what came before.
+    Snapshot {
+        tasks: Vec<TaskMapper<'a>>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<'a> {
+    at: DateTime<Utc>,
+    #[serde(flatten)]
+    event: Event<'a>,
+}
+
+/// Keeps the tasks as a journal: every save appends what changed, one
+/// JSON line per event, and loading replays them from the last snapshot.
+/// The list can also be loaded as it was at a past time.
+pub struct JournalRepository {
+    path: String,
+    as_of: Option<DateTime<Utc>>,
+    /// The list after the last event, which saves are compared against.
+    stored: HashMap<u32, Task>,
+    since_snapshot: usize,
+    /// Set when some lines couldn't be read: the next save first writes
+    /// the journal again without them.
+    repair: bool,
+}
+
+impl JournalRepository {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            as_of: None,
+            stored: HashMap::new(),
+            since_snapshot: 0,
+            repair: false,
+        }
+    }
+
+    /// Loads the list as it was at `time`. Saving it writes it back as the
+    /// current list, without losing the history in between.
+    pub fn with_as_of(mut self, time: DateTime<Utc>) -> Self {
+        self.as_of = Some(time);
+        self
+    }
+
+    /// Rebuilds the list as it was at `time`, or `None` if the journal
+    /// was compacted past it.
+    pub fn load_at(&self, time: DateTime<Utc>) -> io::Result<Option<Vec<Task>>> {
+        let entries = self.read()?;
+
+        if entries.first().is_some_and(|entry| entry.at > time) {
+            // A journal that starts with a snapshot was compacted: what
+            // came before is gone. Otherwise there was nothing yet.
+            let compacted = matches!(entries[0].event, Event::Snapshot { .. });
+
+            return Ok(if compacted { None } else { Some(Vec::new()) });
+        }
+
+        let entries: Vec<Entry> = entries
+            .into_iter()
+            .filter(|entry| entry.at <= time)
+            .collect();
+
+        Ok(Some(ordered(replay(&entries).0)))
+    }
+
+    fn read(&self) -> io::Result<Vec<Entry<'static>>> {
+        match self.read_valid()? {
+            (entries, None) => Ok(entries),
+            (_, Some(error)) => Err(io::Error::new(ErrorKind::InvalidData, error)),
+        }
+    }
+
+    /// Reads the lines that are valid entries, skipping the others, and
+    /// tells why the first one skipped was invalid.
+    fn read_valid(&self) -> io::Result<(Vec<Entry<'static>>, Option<serde_json::Error>)> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok((Vec::new(), None)),
+            Err(error) => return Err(error),
+        };
+
+        let mut entries = Vec::new();
+        let mut invalid = None;
+
+        for line in BufReader::new(file).lines() {
+            match serde_json::from_str(&line?) {
+                Ok(entry) => entries.push(entry),
+                Err(error) => {
+                    invalid.get_or_insert(error);
+                }
+            }
+        }
+
+        Ok((entries, invalid))
+    }
+
+    fn append(&self, entries: &[Entry]) -> io::Result<()> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        write_entries(file, entries)
+    }
+
+    /// Drops everything before the oldest snapshot that is kept.
+    fn compact(&self) -> io::Result<()> {
+        let entries = self.read()?;
+
+        let snapshots: Vec<usize> = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| matches!(entry.event, Event::Snapshot { .. }))
+            .map(|(index, _)| index)
+            .collect();
+
+        if snapshots.len() <= KEPT_SNAPSHOTS {
+            return Ok(());
+        }
+
+        let start = snapshots[snapshots.len() - KEPT_SNAPSHOTS];
+
+        self.replace(&entries[start..])
+    }
+
+    /// Writes the journal again with only its valid lines.
+    fn drop_invalid(&self) -> io::Result<()> {
+        let (entries, _) = self.read_valid()?;
+
+        self.replace(&entries)
+    }
+
+    /// Replaces the journal with `entries`. The file is written aside
+    /// first, so a failure leaves the journal whole.
+    fn replace(&self, entries: &[Entry]) -> io::Result<()> {
+        let replacement = format!("{}.tmp", self.path);
+
+        write_entries(File::create(&replacement)?, entries)?;
+
+        fs::rename(replacement, &self.path)
+    }
+
+    fn write(&mut self, tasks: &[&Task]) -> io::Result<()> {
+        if self.repair {
+            self.drop_invalid()?;
+            self.repair = false;
+        }
+
+        let at = Utc::now();
+        let current: HashSet<u32> = tasks.iter().map(|task| task.id).collect();
+
+        let mut entries: Vec<Entry> = self
+            .stored
+            .keys()
+            .filter(|id| !current.contains(id))
+            .map(|&id| Entry {
+                at,
+                event: Event::Delete { id },
+            })
+            .collect();
+
+        entries.extend(
+            tasks
+                .iter()
+                .filter(|task| self.stored.get(&task.id) != Some(**task))
+                .map(|task| Entry {
+                    at,
+                    event: Event::Put {
+                        task: TaskMapper::from(*task),
+                    },
+                }),
+        );
+
+        self.since_snapshot += entries.len();
+
+        if self.since_snapshot >= SNAPSHOT_EVERY {
+            entries.push(Entry {
+                at,
+                event: Event::Snapshot {
+                    tasks: tasks.iter().map(|task| TaskMapper::from(*task)).collect(),
+                },
+            });
+        }
+
+        self.append(&entries)?;
+
+        if self.since_snapshot >= SNAPSHOT_EVERY {
+            self.since_snapshot = 0;
+
+            // The changes are already written, so a failed compaction
+            // only leaves a longer journal.
+            let _ = self.compact();
+        }
+
+        Ok(())
+    }
+}
+
+impl Repository for JournalRepository {
+    fn save(&mut self, tasks: Vec<&Task>) -> bool {
+        if self.write(&tasks).is_err() {
+            return false;
+        }
+
+        self.stored = tasks
+            .into_iter()
+            .map(|task| (task.id, task.clone()))
+            .collect();
+
+        true
+    }
+
+    fn load(&mut self) -> Result<Vec<Task>, LoadError> {
+        let (entries, invalid) = self
+            .read_valid()
+            .map_err(|error| LoadError::Unreadable(error.to_string()))?;
+
+        let (stored, since_snapshot) = replay(&entries);
+
+        self.stored = stored;
+        self.since_snapshot = since_snapshot;
+
+        if let Some(error) = invalid {
+            self.repair = true;
+
+            return Err(LoadError::Corrupt {
+                reason: error.to_string(),
+                salvaged: ordered(self.stored.clone()),
+            });
+        }
+
+        match self.as_of.map(|time| self.load_at(time)) {
+            Some(Ok(Some(tasks))) => Ok(tasks),
+            Some(Ok(None)) => Err(LoadError::Unreadable(
+                "the journal doesn't go back that far".to_string(),
+            )),
+            Some(Err(error)) => Err(LoadError::Unreadable(error.to_string())),
+            None => Ok(ordered(self.stored.clone())),
+        }
+    }
+}
+
+/// Plays the events from the last snapshot on, and tells how many came
+/// after it.
+fn replay(entries: &[Entry]) -> (HashMap<u32, Task>, usize) {
+    let start = entries
+        .iter()
+        .rposition(|entry| matches!(entry.event, Event::Snapshot { .. }))
+        .unwrap_or(0);
+
+    let mut tasks = HashMap::new();
+
+    for entry in &entries[start..] {
+        match &entry.event {
+            Event::Put { task } => {
+                tasks.insert(task.id, Task::from(task.clone()));
+            }
+            Event::Delete { id } => {
+                tasks.remove(id);
+            }
+            Event::Snapshot { tasks: snapshot } => {
+                tasks = snapshot
+                    .iter()
+                    .map(|task| (task.id, Task::from(task.clone())))
+                    .collect();
+            }
+        }
+    }
+
+    (tasks, entries.len() - start)
+}
+
+fn ordered(tasks: HashMap<u32, Task>) -> Vec<Task> {
+    let mut tasks: Vec<Task> = tasks.into_values().collect();
+
+    tasks.sort_by_key(|task| (task.position, task.id));
+    tasks
+}
+
+fn write_entries(file: File, entries: &[Entry]) -> io::Result<()> {
+    let mut writer = BufWriter::new(file);
+
+    for entry in entries {
+        writeln!(writer, "{}", serde_json::to_string(entry)?)?;
+    }
+
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::{DateTime, TimeZone, Utc};
+    use tempfile::TempDir;
+    use todo::{error::LoadError, repository::Repository, task::Task};
+
+    use super::{Entry, Event, JournalRepository, KEPT_SNAPSHOTS, SNAPSHOT_EVERY};
+    use crate::task_mapper::TaskMapper;
+
+    fn task(id: u32, name: &str) -> Task {
+        Task {
+            id,
+            name: name.to_string(),
+            description: name.to_string(),
+            position: id as u64 * 1024,
+            ..Default::default()
+        }
+    }
+
+    fn journal() -> (TempDir, String) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("tasks.jsonl").to_str().unwrap().to_string();
+
+        (dir, path)
+    }
+
+    fn day(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap()
+    }
+
+    fn put(at: DateTime<Utc>, task: &Task) -> Entry<'_> {
+        Entry {
+            at,
+            event: Event::Put {
+                task: TaskMapper::from(task),
+            },
+        }
+    }
+
+    fn snapshot<'a>(at: DateTime<Utc>, tasks: &'a [Task]) -> Entry<'a> {
+        Entry {
+            at,
+            event: Event::Snapshot {
+                tasks: tasks.iter().map(TaskMapper::from).collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_saves_only_changes() {
+        let (_dir, path) = journal();
+        let first = task(1, "First");
+        let mut second = task(2, "Second");
+        let third = task(3, "Third");
+
+        let mut repository = JournalRepository::new(&path);
+        assert!(repository.save(vec![&first, &second, &third]));
+
+        second.name = "Second again".to_string();
+        assert!(repository.save(vec![&second, &third]));
+
+        let types: Vec<String> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let entry: serde_json::Value = serde_json::from_str(line).unwrap();
+                entry["type"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(types, vec!["put", "put", "put", "delete", "put"]);
+
+        let loaded = JournalRepository::new(&path).load().unwrap();
+        assert_eq!(loaded, vec![second, third]);
+    }
+
+    #[test]
+    fn test_as_of() {
+        let (_dir, path) = journal();
+        let first = task(1, "First");
+        let second = task(2, "Second");
+
+        let repository = JournalRepository::new(&path);
+        repository
+            .append(&[
+                put(day(2), &first),
+                put(day(4), &second),
+                Entry {
+                    at: day(6),
+                    event: Event::Delete { id: 1 },
+                },
+            ])
+            .unwrap();
+
+        assert_eq!(repository.load_at(day(1)).unwrap(), Some(Vec::new()));
+        assert_eq!(
+            repository.load_at(day(3)).unwrap(),
+            Some(vec![first.clone()])
+        );
+        assert_eq!(
+            repository.load_at(day(5)).unwrap(),
+            Some(vec![first.clone(), second.clone()])
+        );
+        assert_eq!(
+            repository.load_at(day(6)).unwrap(),
+            Some(vec![second.clone()])
+        );
+
+        let mut past = JournalRepository::new(&path).with_as_of(day(3));
+        let tasks = past.load().unwrap();
+        assert_eq!(tasks, vec![first.clone()]);
+
+        // Saving the past list brings it back without losing the history.
+        assert!(past.save(tasks.iter().collect()));
+        assert_eq!(
+            JournalRepository::new(&path).load().unwrap(),
+            vec![first.clone()]
+        );
+        assert_eq!(
+            repository.load_at(day(5)).unwrap(),
+            Some(vec![first, second])
+        );
+    }
+
+    #[test]
+    fn test_compaction() {
+        let (_dir, path) = journal();
+        let lists: Vec<Vec<Task>> = (1..=KEPT_SNAPSHOTS as u32)
+            .map(|count| (1..=count).map(|id| task(id, "Kept")).collect())
+            .collect();
+
+        let mut repository = JournalRepository::new(&path);
+        let snapshots: Vec<Entry> = lists
+            .iter()
+            .zip(1..)
+            .map(|(tasks, at)| snapshot(day(at), tasks))
+            .collect();
+        repository.append(&snapshots).unwrap();
+
+        let mut tasks = repository.load().unwrap();
+        assert_eq!(tasks.len(), KEPT_SNAPSHOTS);
+
+        // Enough changes for another snapshot, which drops the oldest.
+        tasks.extend((100..100 + SNAPSHOT_EVERY as u32).map(|id| task(id, "New")));
+        assert!(repository.save(tasks.iter().collect()));
+
+        let snapshots = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .filter(|line| line.contains(r#""type":"snapshot""#))
+            .count();
+        assert_eq!(snapshots, KEPT_SNAPSHOTS);
+
+        assert_eq!(repository.load_at(day(1)).unwrap(), None);
+        assert_eq!(repository.load_at(day(2)).unwrap(), Some(lists[1].clone()));
+        assert_eq!(JournalRepository::new(&path).load().unwrap(), tasks);
+
+        let mut past = JournalRepository::new(&path).with_as_of(day(1));
+        assert!(matches!(past.load(), Err(LoadError::Unreadable(_))));
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        let (_dir, path) = journal();
+        let first = task(1, "First");
+        let second = task(2, "Second");
+
+        let mut repository = JournalRepository::new(&path);
+        assert!(repository.save(vec![&first]));
+
+        let mut data = fs::read_to_string(&path).unwrap();
+        data.push_str("{\"at\": \"not a time\"\n");
+        fs::write(&path, data).unwrap();
+
+        let mut repository = JournalRepository::new(&path);
+        let salvaged = match repository.load() {
+            Err(LoadError::Corrupt { salvaged, .. }) => salvaged,
+            result => panic!("unexpected result {:?}", result),
+        };
+        assert_eq!(salvaged, vec![first.clone()]);
+
+        // The next save leaves the invalid line out.
+        assert!(repository.save(vec![&first, &second]));
+        assert!(!fs::read_to_string(&path).unwrap().contains("not a time"));
+        assert_eq!(
+            JournalRepository::new(&path).load().unwrap(),
+            vec![first, second]
+        );
+    }
+}
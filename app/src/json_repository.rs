@@ -0,0 +1,345 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, ErrorKind},
+    path::Path,
+};
+
+use todo::{
+    custom_action::CustomAction,
+    error::{ActionError, LoadError},
+    repository::Repository,
+    task::Task,
+};
+
+use crate::{
+    document::{self, Document, DocumentError},
+    task_mapper::TaskMapper,
+};
+
+pub const TASKS_FILE: &str = "tasks.json";
+
+pub const RESTORE_BACKUP: &str = "restore-backup";
+
+/// How many backups are kept, as `tasks.json.1` (newest) up to
+/// `tasks.json.3` (oldest).
+const BACKUPS: usize = 3;
+
+/// Keeps the whole list in a JSON file. Saving writes a new file next to
+/// it and renames it over the old one, so a crash leaves one or the other,
+/// never half of each. The file being replaced becomes the newest backup.
+pub struct JsonRepository {
+    path: String,
+}
+
+impl JsonRepository {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+        }
+    }
+
+    fn write(&self, tasks: Vec<&Task>) -> io::Result<()> {
+        let written = format!("{}.tmp", self.path);
+        let mut writer = BufWriter::new(File::create(&written)?);
+        serde_json::to_writer_pretty(&mut writer, &Document::new(tasks))?;
+
+        let file = writer.into_inner().map_err(|error| error.into_error())?;
+        file.sync_all()?;
+
+        self.rotate()?;
+
+        fs::rename(&written, &self.path)?;
+
+        // The tasks are already in place: failing to sync the directory
+        // only risks losing the rename on a crash, not the save itself.
+        let _ = sync_parent(&self.path);
+
+        Ok(())
+    }
+
+    /// Shifts the backups by one, dropping the oldest, and copies the file
+    /// about to be replaced as the newest.
+    fn rotate(&self) -> io::Result<()> {
+        if fs::metadata(&self.path).is_err() {
+            return Ok(());
+        }
+
+        for index in (1..BACKUPS).rev() {
+            let from = backup(&self.path, index);
+
+            if fs::metadata(&from).is_ok() {
+                fs::rename(from, backup(&self.path, index + 1))?;
+            }
+        }
+
+        fs::copy(&self.path, backup(&self.path, 1)).map(|_| ())
+    }
+}
+
+impl Repository for JsonRepository {
+    fn save(&mut self, tasks: Vec<&Task>) -> bool {
+        self.write(tasks).is_ok()
+    }
+
+    fn load(&mut self) -> Result<Vec<Task>, LoadError> {
+        let data = match fs::read_to_string(&self.path) {
+            Ok(data) => data,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(LoadError::Unreadable(error.to_string())),
+        };
+
+        document::read(&data).map_err(|error| match error {
+            DocumentError::Invalid(reason) => LoadError::Corrupt {
+                reason,
+                salvaged: salvage(&data),
+            },
+            DocumentError::Newer(_) => LoadError::Unsupported(error.to_string()),
+        })
+    }
+}
+
+/// The `restore-backup` action: replaces the tasks with those of a backup
+/// of `path`. Like any other change, it is only written on the next save.
+pub fn restore_backup(path: &str) -> CustomAction {
+    let path = path.to_string();
+
+    CustomAction::new(
+        RESTORE_BACKUP,
+        "Replaces the tasks with a saved backup",
+        move |manager, args| {
+            let index = args[0]
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|index| (1..=BACKUPS).contains(index))
+                .ok_or(ActionError::Validation {
+                    field: "backup",
+                    reason: "not a backup number",
+                })?;
+
+            let file = backup(&path, index);
+
+            let tasks = fs::read_to_string(&file)
+                .map_err(|error| error.to_string())
+                .and_then(|data| document::read(&data).map_err(|error| error.to_string()))
+                .map_err(|error| ActionError::Unreadable(format!("{}: {}", file, error)))?;
+
+            manager.set_tasks(tasks);
+
+            Ok(())
+        },
+    )
+    .with_text("backup", "Backup number (1 is the newest)")
+}
+
+/// Reads every task object that is still whole in a damaged file, for
+/// example one cut short by a crash, whatever its version. Only the first
+/// task with each id is kept.
+fn salvage(data: &str) -> Vec<Task> {
+    let mut tasks: Vec<Task> = Vec::new();
+    let mut starts = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (index, character) in data.char_indices() {
+        if in_string {
+            match character {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match character {
+            '"' => in_string = true,
+            '{' => starts.push(index),
+            '}' => {
+                let Some(from) = starts.pop() else {
+                    continue;
+                };
+
+                if let Ok(task) = serde_json::from_str::<TaskMapper>(&data[from..=index]) {
+                    if !tasks.iter().any(|kept| kept.id == task.id) {
+                        tasks.push(task.into());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    tasks
+}
+
+fn backup(path: &str, index: usize) -> String {
+    format!("{}.{}", path, index)
+}
+
+/// Makes the rename itself durable. Directories can only be synced this
+/// way on Unix; elsewhere the rename is left to the file system.
+fn sync_parent(path: &str) -> io::Result<()> {
+    if !cfg!(unix) {
+        return Ok(());
+    }
+
+    let parent = match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    File::open(parent)?.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+    use todo::{
+        error::{ActionError, LoadError},
+        repository::Repository,
+        task::Task,
+        task_manager::TaskManager,
+    };
+
+    use super::{backup, restore_backup, salvage, JsonRepository, BACKUPS};
+
+    fn task(id: u32, name: &str) -> Task {
+        Task {
+            id,
+            name: name.to_string(),
+            description: name.to_string(),
+            position: id as u64 * 1024,
+            ..Default::default()
+        }
+    }
+
+    fn tasks_file() -> (TempDir, String) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("tasks.json").to_str().unwrap().to_string();
+
+        (dir, path)
+    }
+
+    #[test]
+    fn test_save_rotates_backups() {
+        let (dir, path) = tasks_file();
+        let mut repository = JsonRepository::new(&path);
+
+        let saves: Vec<Task> = (1..=5)
+            .map(|id| task(id, &format!("Save {}", id)))
+            .collect();
+
+        for task in &saves {
+            assert!(repository.save(vec![task]));
+        }
+
+        assert_eq!(repository.load().unwrap(), vec![saves[4].clone()]);
+
+        // The newest backup is the file the last save replaced, and only
+        // `BACKUPS` of them are kept.
+        for index in 1..=BACKUPS {
+            let data = fs::read_to_string(backup(&path, index)).unwrap();
+            let saved = JsonRepository::new(&backup(&path, index)).load().unwrap();
+
+            assert!(data.contains("\"version\""));
+            assert_eq!(saved, vec![saves[4 - index].clone()]);
+        }
+
+        assert!(fs::metadata(backup(&path, BACKUPS + 1)).is_err());
+        assert!(fs::metadata(format!("{}.tmp", path)).is_err());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), BACKUPS + 1);
+    }
+
+    #[test]
+    fn test_first_save_has_no_backup() {
+        let (_dir, path) = tasks_file();
+        let mut repository = JsonRepository::new(&path);
+
+        assert_eq!(repository.load().unwrap(), Vec::new());
+        assert!(repository.save(vec![&task(1, "First")]));
+        assert!(fs::metadata(backup(&path, 1)).is_err());
+    }
+
+    #[test]
+    fn test_save_fails_without_directory() {
+        let (dir, _) = tasks_file();
+        let path = dir.path().join("missing").join("tasks.json");
+        let mut repository = JsonRepository::new(path.to_str().unwrap());
+
+        assert!(!repository.save(vec![&task(1, "First")]));
+    }
+
+    #[test]
+    fn test_restore_backup() {
+        let (_dir, path) = tasks_file();
+        let mut repository = JsonRepository::new(&path);
+        let old = task(1, "Old");
+        let new = task(2, "New");
+
+        assert!(repository.save(vec![&old]));
+        assert!(repository.save(vec![&new]));
+
+        let action = restore_backup(&path);
+        let mut manager = TaskManager::new();
+
+        action.run(&mut manager, &["1".to_string()]).unwrap();
+        assert_eq!(manager.get_tasks().cloned().collect::<Vec<_>>(), vec![old]);
+
+        // Nothing is written until the next save.
+        assert_eq!(repository.load().unwrap(), vec![new]);
+
+        for index in ["0", "4", "newest"] {
+            assert!(matches!(
+                action.run(&mut manager, &[index.to_string()]),
+                Err(ActionError::Validation {
+                    field: "backup",
+                    ..
+                })
+            ));
+        }
+
+        assert!(matches!(
+            action.run(&mut manager, &["2".to_string()]),
+            Err(ActionError::Unreadable(_))
+        ));
+    }
+
+    #[test]
+    fn test_salvage() {
+        let data = r#"{"version": 2, "tasks": [
+            {"id": 1, "name": "Braces } in { a name", "description": "\"}\"", "done": false},
+            {"id": 2, "name": "Second", "description": "Second", "done": true},
+            {"id": 1, "name": "Same id", "description": "Same id", "done": false},
+            {"id": 3, "name": "Cut sho"#;
+
+        let tasks = salvage(data);
+        let names: Vec<&str> = tasks.iter().map(|task| task.name.as_str()).collect();
+
+        assert_eq!(names, vec!["Braces } in { a name", "Second"]);
+        assert_eq!(tasks[0].description, "\"}\"");
+        assert!(tasks[1].done);
+
+        assert_eq!(salvage("not json at all"), Vec::new());
+    }
+
+    #[test]
+    fn test_load_damaged_file() {
+        let (_dir, path) = tasks_file();
+        let mut repository = JsonRepository::new(&path);
+        let first = task(1, "First");
+
+        assert!(repository.save(vec![&first, &task(2, "Second")]));
+
+        let data = fs::read_to_string(&path).unwrap();
+        let cut = data.find("Second").unwrap();
+        fs::write(&path, &data[..cut]).unwrap();
+
+        match repository.load() {
+            Err(LoadError::Corrupt { salvaged, .. }) => assert_eq!(salvaged, vec![first]),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
@@ -1,15 +1,41 @@
 use std::{
     env,
     io::{self, Write},
+    process,
 };
 
+use audit::{AuditLog, AUDIT_FILE};
+use chrono::{DateTime, Utc};
+use journal_repository::JournalRepository;
+use json_repository::{restore_backup, JsonRepository, TASKS_FILE};
+use options::{Options, USAGE};
 use prompt::Prompt;
-use task_mapper::TaskMapper;
-use todo::{display::DisplayMessage, reader::Reader, repository::Repository, task::Task};
-
+use script::Script;
+use shortcuts::{Shortcuts, SHORTCUTS_FILE};
+use sqlite_repository::SqliteRepository;
+use todo::{
+    action_manager::ActionMangerBuilder, display::DisplayMessage, reader::Reader,
+    repository::Repository,
+};
+use todotxt_repository::TodoTxtRepository;
+
+mod audit;
+mod csv_file;
+mod document;
+mod ical_file;
+mod journal_repository;
+mod json_repository;
+mod markdown_file;
 mod menu;
+mod options;
 mod prompt;
+mod report;
+mod script;
+mod shortcuts;
+mod sqlite_repository;
 mod task_mapper;
+mod todotxt_repository;
+mod transfer;
 mod translation;
 
 #[derive(Clone)]
@@ -23,79 +49,115 @@ impl DisplayMessage for App {
 }
 
 impl Reader for App {
-    fn read(&mut self) -> String {
+    fn read(&mut self) -> Option<String> {
         let mut input = String::new();
 
-        io::stdin()
+        let read = io::stdin()
             .read_line(&mut input)
             .expect("Failed to read line");
 
-        let input = input.replace("\n", "").to_owned();
-        input
-    }
-}
-
-impl Repository for App {
-    fn save(&mut self, tasks: Vec<Task>) -> bool {
-        let file = std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open("tasks.json");
-
-        let tasks_mapper: Vec<_> = tasks
-            .into_iter()
-            .map(|task| TaskMapper::from(task))
-            .collect();
-
-        match file {
-            Ok(file) => {
-                serde_json::to_writer_pretty(file, &tasks_mapper).unwrap();
-                true
-            }
-
-            Err(_) => false,
+        if read == 0 {
+            return None;
         }
-    }
 
-    fn load(&mut self) -> Vec<Task> {
-        match std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .read(true)
-            .open("tasks.json")
-        {
-            Ok(file) => {
-                let tasks_mapper: Vec<TaskMapper> = match serde_json::from_reader(file) {
-                    Ok(tasks) => tasks,
-                    Err(e) => panic!("An error occurred: {}", e),
-                };
-
-                let tasks: Vec<Task> = tasks_mapper.into_iter().map(|task| task.into()).collect();
-                tasks
-            }
-            Err(_) => Vec::new(),
-        }
+        Some(input.replace("\n", ""))
     }
 }
 
 fn main() {
     let app = App;
 
-    let args: Vec<String> = env::args().collect();
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let reader: Box<dyn Reader> = match &options.script {
+        Some(file) => match Script::open(file) {
+            Ok(script) => Box::new(script),
+            Err(error) => {
+                eprintln!("Couldn't read {}: {}", file, error);
+                process::exit(2);
+            }
+        },
+        None => Box::new(app.clone()),
+    };
 
-    let mut file = None;
+    let shortcuts_file = options.shortcuts.as_deref().unwrap_or(SHORTCUTS_FILE);
 
-    if args.len() > 1 {
-        file = Some(args[1].as_str());
+    let shortcuts = match Shortcuts::load(shortcuts_file) {
+        Ok(shortcuts) => shortcuts,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(2);
+        }
+    };
+
+    let repository: Box<dyn Repository> =
+        match (&options.sqlite, &options.journal, &options.todotxt) {
+            (Some(file), _, _) => match SqliteRepository::open(file) {
+                Ok(repository) => Box::new(repository),
+                Err(error) => {
+                    eprintln!("Couldn't open {}: {}", file, error);
+                    process::exit(2);
+                }
+            },
+            (_, Some(file), _) => Box::new(open_journal(file, options.as_of)),
+            (_, _, Some(file)) => Box::new(TodoTxtRepository::new(file)),
+            _ => Box::new(JsonRepository::new(TASKS_FILE)),
+        };
+
+    let mut action_manager = ActionMangerBuilder::new(repository)
+        .with_middleware(Box::new(AuditLog::new(AUDIT_FILE)))
+        .build();
+
+    // Only the JSON file keeps backups.
+    if options.sqlite.is_none() && options.journal.is_none() && options.todotxt.is_none() {
+        action_manager
+            .register(restore_backup(TASKS_FILE))
+            .expect("restore-backup is a valid action");
     }
 
     let mut prompt = Prompt::new(
-        file,
+        options.translation.as_deref(),
         Box::new(app.clone()),
-        Box::new(app.clone()),
-        Box::new(app.clone()),
-    );
+        reader,
+        action_manager,
+    )
+    .with_shortcuts(shortcuts);
+
+    if options.script.is_some() {
+        prompt = prompt.with_script(options.keep_going);
+    }
 
-    prompt.run();
+    let succeeded = prompt.run();
+
+    // Mistyped commands are part of an interactive session; only a script
+    // reports its failures in the exit code.
+    if options.script.is_some() && !succeeded {
+        process::exit(1);
+    }
+}
+
+fn open_journal(file: &str, as_of: Option<DateTime<Utc>>) -> JournalRepository {
+    let journal = JournalRepository::new(file);
+
+    let Some(time) = as_of else {
+        return journal;
+    };
+
+    match journal.load_at(time) {
+        Ok(Some(_)) => journal.with_as_of(time),
+        Ok(None) => {
+            eprintln!("{} doesn't go back to {}", file, time);
+            process::exit(2);
+        }
+        Err(error) => {
+            eprintln!("Couldn't read {}: {}", file, error);
+            process::exit(2);
+        }
+    }
 }
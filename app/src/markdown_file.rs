@@ -0,0 +1,249 @@
+use todo::task::Task;
+
+use crate::transfer::{Groups, Imported, RowError};
+
+/// Writes the tasks as a GitHub-flavoured checklist, each description
+/// indented under its task. With `groups`, open and done tasks go under
+/// their own heading.
+pub fn export(tasks: &[Task], groups: Option<&Groups>) -> String {
+    let Some(groups) = groups else {
+        return checklist(tasks.iter());
+    };
+
+    let open = tasks.iter().filter(|task| !task.done);
+    let done = tasks.iter().filter(|task| task.done);
+
+    format!(
+        "## {}\n\n{}\n## {}\n\n{}",
+        groups.open,
+        checklist(open),
+        groups.done,
+        checklist(done)
+    )
+}
+
+fn checklist<'a>(tasks: impl Iterator<Item = &'a Task>) -> String {
+    tasks
+        .map(|task| {
+            let mut item = format!(
+                "- [{}] {}\n",
+                if task.done { 'x' } else { ' ' },
+                task.name.replace('\n', " ")
+            );
+
+            for line in task.description.lines() {
+                item += &format!("  {}\n", line);
+            }
+
+            item
+        })
+        .collect()
+}
+
+/// Reads every checklist item (`- [ ]`, `* [x]`, `1. [ ]`...) as a task,
+/// checked ones as done. The indented lines under an item are its
+/// description; an item without one uses its name. Headings and any other
+/// text are skipped.
+pub fn import(data: &str) -> Imported {
+    let mut imported = Imported::default();
+    // The item being read: line, name, done and description lines.
+    let mut current: Option<(u64, String, bool, Vec<String>)> = None;
+
+    for (index, line) in data.lines().enumerate() {
+        let number = index as u64 + 1;
+
+        if let Some((done, name)) = item(line) {
+            finish(&mut imported, current.take());
+            current = Some((number, name.to_string(), done, Vec::new()));
+            continue;
+        }
+
+        match &mut current {
+            Some((_, _, _, description)) if is_indented(line) || line.trim().is_empty() => {
+                description.push(line.trim().to_string())
+            }
+            _ => finish(&mut imported, current.take()),
+        }
+    }
+
+    finish(&mut imported, current);
+
+    imported
+}
+
+fn finish(imported: &mut Imported, item: Option<(u64, String, bool, Vec<String>)>) {
+    let Some((line, name, done, description)) = item else {
+        return;
+    };
+
+    let name = name.trim();
+
+    if name.is_empty() {
+        imported.errors.push(RowError {
+            line,
+            reason: "the name is empty".to_string(),
+        });
+        return;
+    }
+
+    let description = description.join("\n").trim().to_string();
+
+    imported.tasks.push(Task {
+        name: name.to_string(),
+        description: if description.is_empty() {
+            name.to_string()
+        } else {
+            description
+        },
+        done,
+        ..Default::default()
+    });
+}
+
+/// Reads `- [ ] name`, with any bullet or number and indentation, as
+/// whether it is checked and the name.
+fn item(line: &str) -> Option<(bool, &str)> {
+    let line = line.trim_start();
+
+    let rest = match line.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = line.find(|c: char| !c.is_ascii_digit()).unwrap_or(0);
+
+            if digits == 0 {
+                return None;
+            }
+
+            line[digits..].strip_prefix(['.', ')'])?
+        }
+    };
+
+    let rest = rest.strip_prefix(' ')?.trim_start();
+
+    let (done, name) = if let Some(name) = rest.strip_prefix("[ ]") {
+        (false, name)
+    } else if let Some(name) = rest
+        .strip_prefix("[x]")
+        .or_else(|| rest.strip_prefix("[X]"))
+    {
+        (true, name)
+    } else {
+        return None;
+    };
+
+    match name.chars().next() {
+        None | Some(' ') | Some('\t') => Some((done, name)),
+        Some(_) => None,
+    }
+}
+
+fn is_indented(line: &str) -> bool {
+    line.starts_with("  ") || line.starts_with('\t')
+}
+
+#[cfg(test)]
+mod tests {
+    use todo::task::Task;
+
+    use super::{export, import};
+    use crate::transfer::Groups;
+
+    fn task(name: &str, description: &str, done: bool) -> Task {
+        Task {
+            name: name.to_string(),
+            description: description.to_string(),
+            done,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_export() {
+        let tasks = [
+            task("Shop", "Milk\nBread", false),
+            task("Call", "Call", true),
+        ];
+
+        assert_eq!(
+            export(&tasks, None),
+            "- [ ] Shop\n  Milk\n  Bread\n- [x] Call\n  Call\n"
+        );
+
+        let groups = Groups {
+            open: "To do".to_string(),
+            done: "Done".to_string(),
+        };
+
+        assert_eq!(
+            export(&tasks, Some(&groups)),
+            "## To do\n\n- [ ] Shop\n  Milk\n  Bread\n\n## Done\n\n- [x] Call\n  Call\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tasks = vec![
+            task("Shop", "Milk\n\nBread", false),
+            task("Call", "Call", true),
+        ];
+        let groups = Groups {
+            open: "Open".to_string(),
+            done: "Done".to_string(),
+        };
+
+        for groups in [None, Some(&groups)] {
+            let imported = import(&export(&tasks, groups));
+
+            assert!(imported.errors.is_empty());
+            assert_eq!(imported.tasks, tasks);
+        }
+    }
+
+    #[test]
+    fn test_import() {
+        let data = "# Week\n\
+                    \n\
+                    Some notes.\n\
+                    * [X] Starred\n\
+                    + [ ] Plus\n\
+                    \x20 1. [ ] Nested\n\
+                    12) [x] Numbered\n\
+                    \tIndented with a tab\n\
+                    - [ ]Not an item\n\
+                    - [?] Not either\n\
+                    - Plain bullet\n";
+
+        let imported = import(data);
+        let items: Vec<(&str, &str, bool)> = imported
+            .tasks
+            .iter()
+            .map(|task| (task.name.as_str(), task.description.as_str(), task.done))
+            .collect();
+
+        assert_eq!(
+            items,
+            vec![
+                ("Starred", "Starred", true),
+                ("Plus", "Plus", false),
+                ("Nested", "Nested", false),
+                ("Numbered", "Indented with a tab", true),
+            ]
+        );
+        assert!(imported.errors.is_empty());
+    }
+
+    #[test]
+    fn test_empty_name() {
+        let imported = import("- [ ] First\n- [x]   \n  Lost description\n- [ ] Last\n");
+
+        let names: Vec<&str> = imported
+            .tasks
+            .iter()
+            .map(|task| task.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["First", "Last"]);
+
+        let errors: Vec<String> = imported.errors.iter().map(ToString::to_string).collect();
+        assert_eq!(errors, vec!["line 2: the name is empty"]);
+    }
+}
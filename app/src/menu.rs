@@ -12,15 +12,38 @@ pub fn menu_logo() -> String {
     .to_string()
 }
 
-pub fn menu_show(translation: &Translation) -> String {
+/// Shows the built-in commands followed by the `extra` ones, given as
+/// name and help text.
+pub fn menu_show(translation: &Translation, extra: &[(String, String)]) -> String {
+    let custom: String = extra
+        .iter()
+        .map(|(name, help)| format!("{:<9}{}\n", capitalize(name), help))
+        .collect();
+
     format!(
-        "{}{}{}{}{}{}{}",
+        "{}{}{}{}{}{}{}{}{}{}{}{}{}{}",
         translation.get_message("menu.add"),
         translation.get_message("menu.remove"),
         translation.get_message("menu.update"),
         translation.get_message("menu.display"),
         translation.get_message("menu.complete"),
+        translation.get_message("menu.move"),
+        translation.get_message("menu.merge"),
         translation.get_message("menu.save"),
+        translation.get_message("menu.stats"),
+        translation.get_message("menu.import"),
+        translation.get_message("menu.export"),
+        translation.get_message("menu.audit"),
+        custom,
         translation.get_message("menu.exit")
     )
 }
+
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,89 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+pub const USAGE: &str = "Usage: app [translation file] [--script <file> [--keep-going]] \
+                          [--shortcuts <file>] [--sqlite <file> | --journal <file> [--as-of <time>] | --todotxt <file>]";
+
+/// What `main` was asked to do, read from the command line.
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub translation: Option<String>,
+    pub script: Option<String>,
+    pub keep_going: bool,
+    pub shortcuts: Option<String>,
+    /// Keeps the tasks in this SQLite database instead of `tasks.json`.
+    pub sqlite: Option<String>,
+    /// Keeps the tasks in this journal instead of `tasks.json`.
+    pub journal: Option<String>,
+    /// Loads the journal as it was at this time.
+    pub as_of: Option<DateTime<Utc>>,
+    /// Keeps the tasks in this todo.txt file instead of `tasks.json`.
+    pub todotxt: Option<String>,
+}
+
+impl Options {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--script" => match args.next() {
+                    Some(file) => options.script = Some(file),
+                    None => return Err("--script needs a file".to_string()),
+                },
+                "--keep-going" => options.keep_going = true,
+                "--shortcuts" => match args.next() {
+                    Some(file) => options.shortcuts = Some(file),
+                    None => return Err("--shortcuts needs a file".to_string()),
+                },
+                "--sqlite" => match args.next() {
+                    Some(file) => options.sqlite = Some(file),
+                    None => return Err("--sqlite needs a file".to_string()),
+                },
+                "--journal" => match args.next() {
+                    Some(file) => options.journal = Some(file),
+                    None => return Err("--journal needs a file".to_string()),
+                },
+                "--todotxt" => match args.next() {
+                    Some(file) => options.todotxt = Some(file),
+                    None => return Err("--todotxt needs a file".to_string()),
+                },
+                "--as-of" => match args.next() {
+                    Some(time) => options.as_of = Some(parse_time(&time)?),
+                    None => return Err("--as-of needs a time".to_string()),
+                },
+                _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+                _ if options.translation.is_none() => options.translation = Some(arg),
+                _ => return Err(format!("Unexpected argument {}", arg)),
+            }
+        }
+
+        if options.keep_going && options.script.is_none() {
+            return Err("--keep-going only works with --script".to_string());
+        }
+
+        let storages = [&options.sqlite, &options.journal, &options.todotxt];
+
+        if storages.iter().filter(|storage| storage.is_some()).count() > 1 {
+            return Err("Only one of --sqlite, --journal and --todotxt can be used".to_string());
+        }
+
+        if options.as_of.is_some() && options.journal.is_none() {
+            return Err("--as-of only works with --journal".to_string());
+        }
+
+        Ok(options)
+    }
+}
+
+/// Reads an RFC 3339 time, or a date meaning the end of that day (UTC).
+fn parse_time(time: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(time) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    NaiveDate::parse_from_str(time, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(23, 59, 59))
+        .map(|time| time.and_utc())
+        .ok_or_else(|| format!("Invalid time {}", time))
+}
@@ -1,16 +1,26 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+};
 
 use colored::Colorize;
 use todo::{
-    action_args::{ActionArgs, ActionArgsBuilder},
     action_manager::ActionManger,
+    command::Command,
+    custom_action::{Argument, ArgumentKind},
     display::DisplayMessage,
+    error::{ActionError, LoadError},
     reader::Reader,
-    repository::Repository,
+    task_manager::Placement,
 };
 
 use crate::{
+    audit::{read_entries, AUDIT_FILE},
+    json_repository::RESTORE_BACKUP,
     menu::{menu_logo, menu_show},
+    report::{diff_report, outcome_report, task_line},
+    shortcuts::{Macro, Shortcuts},
+    transfer::{self, Format, Groups},
     translation::Translation,
 };
 
@@ -24,10 +34,17 @@ pub struct Prompt {
     run: bool,
     modifications: bool,
     translation: Translation,
+    interactive: bool,
+    keep_going: bool,
+    failures: usize,
+    shortcuts: Shortcuts,
+    /// Words typed after the command, used before asking for more.
+    inline: VecDeque<String>,
 }
 
 enum Style {
     Error,
+    Warning,
     Success,
     Fancy,
     Default,
@@ -38,19 +55,37 @@ impl Prompt {
         file: Option<&str>,
         display: Box<dyn DisplayMessage>,
         reader: Box<dyn Reader>,
-        repository: Box<dyn Repository>,
+        action_manager: ActionManger,
     ) -> Self {
         Self {
             display,
             reader,
             commands: Self::commands_mapper(),
             run: true,
-            action_manager: ActionManger::new(repository),
+            action_manager,
             modifications: false,
             translation: Translation::new(file),
+            interactive: true,
+            keep_going: true,
+            failures: 0,
+            shortcuts: Shortcuts::default(),
+            inline: VecDeque::new(),
         }
     }
 
+    pub fn with_shortcuts(mut self, shortcuts: Shortcuts) -> Self {
+        self.shortcuts = shortcuts;
+        self
+    }
+
+    /// Runs without showing the menu, for input that comes from a script.
+    /// Unless `keep_going` is set, the first failure stops it.
+    pub fn with_script(mut self, keep_going: bool) -> Self {
+        self.interactive = false;
+        self.keep_going = keep_going;
+        self
+    }
+
     fn commands_mapper() -> HashMap<&'static str, CommandHandler> {
         let mut commands: HashMap<&'static str, CommandHandler> = HashMap::new();
 
@@ -59,25 +94,144 @@ impl Prompt {
         commands.insert("remove", Prompt::command_remove);
         commands.insert("update", Prompt::command_update);
         commands.insert("complete", Prompt::command_complete);
+        commands.insert("move", Prompt::command_move);
+        commands.insert("merge", Prompt::command_merge);
         commands.insert("save", Prompt::command_save);
+        commands.insert("stats", Prompt::command_stats);
+        commands.insert("audit", Prompt::command_audit);
+        commands.insert("import", Prompt::command_import);
+        commands.insert("export", Prompt::command_export);
         commands.insert("exit", Prompt::command_exit);
 
         commands
     }
 
-    pub fn run(&mut self) {
+    /// Reads commands until `exit` or the end of the input. Returns false
+    /// if any of them failed.
+    pub fn run(&mut self) -> bool {
         let logo = menu_logo();
-        let menu = menu_show(&self.translation);
+        let menu = menu_show(&self.translation, &self.menu_extra());
+
+        if let Some(error) = self.action_manager.take_load_error() {
+            self.recover(error);
+        }
 
         while self.run {
-            self.print(logo.as_str(), Style::Fancy);
-            self.print(menu.as_str(), Style::Default);
+            if self.interactive {
+                self.print(logo.as_str(), Style::Fancy);
+                self.print(menu.as_str(), Style::Default);
+            }
 
             self.show();
 
-            let command = self.read_command();
+            match self.reader.read() {
+                // A blank line where a command is expected is skipped, so
+                // scripts can separate their steps. Answers can still be
+                // blank.
+                Some(input) if input.trim().is_empty() => {}
+                Some(input) => {
+                    let mut words = input.split_whitespace().map(String::from);
+                    let command = words.next().unwrap_or_default().to_lowercase();
 
-            self.process_command(command.as_str());
+                    self.inline = words.collect();
+                    self.process_command(command.as_str());
+                    self.inline.clear();
+                }
+                None => self.run = false,
+            }
+        }
+
+        self.failures == 0
+    }
+
+    /// Reports why the tasks couldn't be loaded and asks how to go on,
+    /// before anything can overwrite what is stored.
+    fn recover(&mut self, error: LoadError) {
+        let (message, salvaged) = match error {
+            LoadError::Unsupported(reason) => {
+                let message = self
+                    .translation
+                    .get_message("load.unsupported")
+                    .replace("{}", &reason);
+
+                // Anything saved would replace a file this version can't
+                // read, so it doesn't start at all.
+                self.print(message.as_str(), Style::Error);
+                self.failures += 1;
+                self.run = false;
+                return;
+            }
+            LoadError::Unreadable(reason) => (
+                self.translation
+                    .get_message("load.unreadable")
+                    .replace("{}", &reason),
+                None,
+            ),
+            LoadError::Corrupt { reason, salvaged } => (
+                self.translation
+                    .get_message("load.corrupt")
+                    .replace("{}", &reason)
+                    + &self
+                        .translation
+                        .get_message("load.salvage")
+                        .replace("{}", &salvaged.len().to_string()),
+                Some(salvaged),
+            ),
+        };
+
+        self.print(message.as_str(), Style::Warning);
+
+        let restore = self.action_manager.custom_action(RESTORE_BACKUP).is_some();
+
+        let mut options = vec!["empty"];
+        if restore {
+            options.push("restore");
+        }
+        if salvaged.is_some() {
+            options.push("salvage");
+        }
+
+        let question = self
+            .translation
+            .get_message("load.question")
+            .replace("{}", &options.join("/"));
+
+        while self.run {
+            self.print(question.as_str(), Style::Default);
+
+            let input = self.read().to_lowercase();
+
+            match input.as_str() {
+                "empty" => return,
+                "restore" if restore => {
+                    let failures = self.failures;
+                    let arguments = self
+                        .action_manager
+                        .custom_action(RESTORE_BACKUP)
+                        .map(|action| action.arguments().to_vec())
+                        .unwrap_or_default();
+
+                    self.command_custom(RESTORE_BACKUP.to_string(), arguments);
+
+                    if self.failures == failures {
+                        return;
+                    }
+                }
+                "salvage" if salvaged.is_some() => {
+                    let tasks = salvaged.unwrap_or_default();
+                    let message = self
+                        .translation
+                        .get_message("success.load")
+                        .replace("{}", &tasks.len().to_string());
+
+                    self.action_manager.recover(tasks);
+                    self.modifications = true;
+                    self.print(message.as_str(), Style::Success);
+                    return;
+                }
+                _ if !self.run => {}
+                _ => self.fail(self.translation.get_message("error.option").as_str()),
+            }
         }
     }
 
@@ -85,42 +239,269 @@ impl Prompt {
         self.print("(todo) > ", Style::Fancy);
     }
 
+    /// Reads an answer. If the input is over, the command asking for it
+    /// counts as failed and the prompt stops.
     fn read(&mut self) -> String {
-        self.reader.read()
+        match self.reader.read() {
+            Some(input) => input,
+            None => {
+                self.failures += 1;
+                self.run = false;
+                String::new()
+            }
+        }
     }
 
-    fn read_command(&mut self) -> String {
-        self.read().to_lowercase()
+    fn fail(&mut self, message: &str) {
+        self.print(message, Style::Error);
+        self.failures += 1;
+
+        if !self.keep_going {
+            self.run = false;
+        }
+    }
+
+    /// Lists the registered actions and the macros for the menu.
+    fn menu_extra(&self) -> Vec<(String, String)> {
+        let mut macros: Vec<(String, String)> = self
+            .shortcuts
+            .macros
+            .iter()
+            .map(|(name, definition)| (name.clone(), definition.help.clone()))
+            .collect();
+        macros.sort();
+
+        self.action_manager
+            .custom_actions()
+            .map(|action| {
+                let help = self
+                    .translation
+                    .find_message(&format!("action.{}", action.name()))
+                    .unwrap_or_else(|| action.help().to_string());
+
+                (action.name().to_string(), help)
+            })
+            .chain(macros)
+            .collect()
     }
 
     fn process_command(&mut self, command: &str) {
+        let command = self.shortcuts.resolve(command).to_string();
+        let command = command.as_str();
+
         match self.commands.get(&command) {
             Some(f) => {
                 f(self);
             }
-            None => self.print(
-                self.translation.get_message("error.command").as_str(),
-                Style::Error,
-            ),
+            None => {
+                if let Some(definition) = self.shortcuts.macros.get(command).cloned() {
+                    self.command_macro(definition);
+                } else if let Some(action) = self.action_manager.custom_action(command) {
+                    let arguments = action.arguments().to_vec();
+
+                    self.command_custom(command.to_string(), arguments);
+                } else {
+                    self.fail(self.translation.get_message("error.command").as_str());
+                }
+            }
         }
     }
 
+    /// Asks for the macro parameters and runs its steps as one transaction,
+    /// so a failing step leaves the tasks as they were.
+    fn command_macro(&mut self, definition: Macro) {
+        let mut values = Vec::new();
+
+        for param in &definition.params {
+            let message = self
+                .translation
+                .get_message("macro.param")
+                .replace("{}", param);
+
+            self.print(message.as_str(), Style::Default);
+            values.push(self.read());
+
+            if !self.run {
+                return;
+            }
+        }
+
+        let result = definition
+            .expand(&values)
+            .and_then(|list| self.action_manager.process_all(list));
+
+        match result {
+            Ok(_) => {
+                self.modifications = true;
+                self.print(
+                    self.translation.get_message("success.macro").as_str(),
+                    Style::Success,
+                );
+            }
+            Err(error) => self.report(&error),
+        }
+    }
+
+    /// Asks for the arguments a registered action declared, using their
+    /// help text as the question, and runs it.
+    fn command_custom(&mut self, name: String, arguments: Vec<Argument>) {
+        let mut args = Vec::new();
+
+        for argument in arguments {
+            let message = self
+                .translation
+                .find_message(&format!("action.{}.{}", name, argument.name))
+                .unwrap_or_else(|| format!("{}: ", argument.help));
+
+            match argument.kind {
+                ArgumentKind::Id => match self.ask_id(message.as_str()) {
+                    Some(id) => args.push(id.to_string()),
+                    None => return,
+                },
+                ArgumentKind::Text => {
+                    args.push(self.answer(message.as_str()));
+
+                    if !self.run {
+                        return;
+                    }
+                }
+            }
+        }
+
+        self.execute(Command::Custom { name, args }, "success.custom");
+    }
+
     fn command_add(&mut self) {
-        let args = self.get_args().with_command("add").build();
+        let (name, description) = self.ask_task();
+
+        self.warn_similar(name.as_str());
+
+        self.confirm(
+            Command::Add { name, description },
+            "question.task.add",
+            "success.task.add",
+        );
+    }
 
-        if self.wanna_proceed(self.translation.get_message("question.task.add").as_str()) == true {
-            if self.action_manager.process(args, &*self.display) == true {
+    /// Shows what the command would change, then runs it if the user
+    /// agrees. A command that would fail is reported without asking.
+    fn confirm(&mut self, command: Command, question: &'static str, success: &'static str) {
+        let diff = match self.action_manager.dry_run(command.clone()) {
+            Ok(diff) => diff,
+            Err(error) => {
+                self.report(&error);
+                return;
+            }
+        };
+
+        self.print(
+            self.translation.get_message("preview.title").as_str(),
+            Style::Default,
+        );
+
+        if diff.is_empty() {
+            self.print(
+                self.translation.get_message("preview.none").as_str(),
+                Style::Warning,
+            );
+        } else {
+            self.print(diff_report(&diff).as_str(), Style::Fancy);
+        }
+
+        self.print("\n", Style::Default);
+
+        if self.wanna_proceed(self.translation.get_message(question).as_str()) {
+            self.execute(command, success);
+        }
+    }
+
+    /// Runs a command that changes the tasks, printing the given success
+    /// message or the reason it failed.
+    fn execute(&mut self, command: Command, success: &'static str) {
+        match self.action_manager.process(command) {
+            Ok(_) => {
                 self.modifications = true;
                 self.print(
-                    self.translation.get_message("success.task.add").as_str(),
+                    self.translation.get_message(success).as_str(),
                     Style::Success,
                 );
             }
+            Err(error) => self.report(&error),
+        }
+    }
+
+    fn report(&mut self, error: &ActionError) {
+        let message = match error {
+            ActionError::NotFound(id) => self
+                .translation
+                .get_message("error.not_found")
+                .replace("{}", &id.to_string()),
+            ActionError::InvalidId(id) => self
+                .translation
+                .get_message("error.invalid_id")
+                .replace("{}", id),
+            ActionError::Validation {
+                field: "name",
+                reason: "empty",
+            } => self.translation.get_message("error.validation.name"),
+            ActionError::Validation {
+                field: "description",
+                reason: "empty",
+            } => self.translation.get_message("error.validation.description"),
+            ActionError::Validation {
+                reason: "same task",
+                ..
+            } => self.translation.get_message("error.validation.same"),
+            ActionError::Validation { field, .. } => self
+                .translation
+                .get_message("error.validation")
+                .replace("{}", field),
+            ActionError::UnknownCommand(_) => self.translation.get_message("error.command"),
+            ActionError::Storage(reason) => self
+                .translation
+                .get_message("error.storage")
+                .replace("{}", reason),
+            ActionError::Unreadable(reason) => self
+                .translation
+                .get_message("error.unreadable")
+                .replace("{}", reason),
+            ActionError::Rejected(reason) => self
+                .translation
+                .get_message("error.rejected")
+                .replace("{}", reason),
+            ActionError::TransactionOpen => self.translation.get_message("error.transaction.open"),
+            ActionError::NoTransaction => self.translation.get_message("error.transaction.none"),
+        };
+
+        self.fail(message.as_str());
+    }
+
+    fn warn_similar(&mut self, name: &str) {
+        let similar: Vec<String> = self
+            .action_manager
+            .similar_tasks(name)
+            .iter()
+            .map(|task| task_line(task))
+            .collect();
+
+        if !similar.is_empty() {
+            self.print(
+                self.translation
+                    .get_message("warning.task.similar")
+                    .as_str(),
+                Style::Warning,
+            );
+
+            for task in similar {
+                self.print(task.as_str(), Style::Warning);
+            }
+
+            self.print("\n", Style::Default);
         }
     }
 
     fn wanna_proceed(&mut self, message: &str) -> bool {
-        loop {
+        while self.run {
             self.print(message, Style::Default);
 
             let input = self.read();
@@ -134,17 +515,15 @@ impl Prompt {
                     );
                     return false;
                 }
-                _ => {
-                    self.print(
-                        self.translation.get_message("error.option").as_str(),
-                        Style::Error,
-                    );
-                }
+                _ if !self.run => {}
+                _ => self.fail(self.translation.get_message("error.option").as_str()),
             }
         }
+
+        false
     }
 
-    fn get_args(&mut self) -> ActionArgsBuilder {
+    fn ask_task(&mut self) -> (String, String) {
         self.print(
             self.translation.get_message("task.name").as_str(),
             Style::Default,
@@ -157,54 +536,38 @@ impl Prompt {
         );
         let description = self.read();
 
-        ActionArgsBuilder::new()
-            .with_first(name)
-            .with_second(description)
+        (name, description)
     }
 
-    fn command_display(&mut self) {
-        let args = ActionArgs::new("display");
+    /// Runs a command that only shows something, and shows it.
+    fn show_outcome(&mut self, command: Command) {
+        match self.action_manager.process(command) {
+            Ok(outcome) => {
+                let report = outcome_report(&outcome, &self.translation);
+
+                self.print(report.as_str(), Style::Default);
+            }
+            Err(error) => self.report(&error),
+        }
+    }
 
-        self.action_manager.process(args, &*self.display);
+    fn command_display(&mut self) {
+        self.show_outcome(Command::Display);
     }
 
     fn command_remove(&mut self) {
-        match self.ask_id(self.translation.get_message("id.remove").as_str()) {
-            Some(id) => {
-                let args = ActionArgsBuilder::new()
-                    .with_command("remove")
-                    .with_first(id)
-                    .build();
-
-                if self.wanna_proceed(
-                    self.translation
-                        .get_message("question.task.remove")
-                        .as_str(),
-                ) == true
-                {
-                    if self.action_manager.process(args, &*self.display) == true {
-                        self.print(
-                            self.translation.get_message("success.task.remove").as_str(),
-                            Style::Success,
-                        );
-                        self.modifications = true;
-                    } else {
-                        self.print(
-                            self.translation.get_message("error.task.remove").as_str(),
-                            Style::Error,
-                        );
-                    }
-                }
-            }
-            None => (),
+        if let Some(id) = self.ask_id(self.translation.get_message("id.remove").as_str()) {
+            self.confirm(
+                Command::Remove { id },
+                "question.task.remove",
+                "success.task.remove",
+            );
         }
     }
 
-    fn ask_id(&mut self, message: &str) -> Option<String> {
-        loop {
-            self.print(message, Style::Default);
-
-            let input = self.read();
+    fn ask_id(&mut self, message: &str) -> Option<u32> {
+        while self.run {
+            let input = self.answer(message);
 
             match input.as_str() {
                 "exit" => {
@@ -215,107 +578,322 @@ impl Prompt {
                     return None;
                 }
 
-                _ => match input.parse::<u32>().is_ok() {
-                    true => {
-                        return Some(input);
+                _ if !self.run => {}
+                _ => match input.parse::<u32>() {
+                    Ok(id) => {
+                        return Some(id);
                     }
-                    false => self.print(
-                        self.translation.get_message("error.task.id").as_str(),
-                        Style::Error,
-                    ),
+                    Err(_) => self.fail(self.translation.get_message("error.task.id").as_str()),
                 },
             }
         }
+
+        None
     }
 
     fn command_update(&mut self) {
-        match self.ask_id(self.translation.get_message("id.update").as_str()) {
-            Some(id) => {
-                let args = self
-                    .get_args()
-                    .with_command("update")
-                    .with_third(id)
-                    .build();
-
-                if self.wanna_proceed(
-                    self.translation
-                        .get_message("question.task.update")
-                        .as_str(),
-                ) == true
-                {
-                    if self.action_manager.process(args, &*self.display) == true {
-                        self.print(
-                            self.translation.get_message("success.task.update").as_str(),
-                            Style::Success,
-                        );
-                        self.modifications = true;
-                    } else {
-                        self.print(
-                            self.translation.get_message("error.task.update").as_str(),
-                            Style::Error,
-                        );
-                    }
-                }
-            }
-            None => (),
+        if let Some(id) = self.ask_id(self.translation.get_message("id.update").as_str()) {
+            let (name, description) = self.ask_task();
+
+            let command = Command::Update {
+                id,
+                name,
+                description,
+            };
+
+            self.confirm(command, "question.task.update", "success.task.update");
         }
     }
 
     fn command_complete(&mut self) {
-        match self.ask_id(self.translation.get_message("id.complete").as_str()) {
-            Some(id) => {
-                let args = ActionArgsBuilder::new()
-                    .with_command("complete")
-                    .with_first(id)
-                    .build();
-
-                if self.wanna_proceed(
-                    self.translation
-                        .get_message("question.task.complete")
-                        .as_str(),
-                ) == true
-                {
-                    if self.action_manager.process(args, &*self.display) == true {
-                        self.print(
-                            self.translation
-                                .get_message("success.task.complete")
-                                .as_str(),
-                            Style::Success,
-                        );
-                        self.modifications = true;
-                    } else {
-                        self.print(
-                            self.translation.get_message("error.task.complete").as_str(),
-                            Style::Error,
-                        );
-                    }
+        if let Some(id) = self.ask_id(self.translation.get_message("id.complete").as_str()) {
+            self.confirm(
+                Command::Complete { id },
+                "question.task.complete",
+                "success.task.complete",
+            );
+        }
+    }
+
+    fn command_move(&mut self) {
+        if let Some(id) = self.ask_id(self.translation.get_message("id.move").as_str()) {
+            let placement = match self.ask_placement() {
+                Some(placement) => placement,
+                None => return,
+            };
+
+            self.confirm(
+                Command::Move { id, placement },
+                "question.task.move",
+                "success.task.move",
+            );
+        }
+    }
+
+    fn ask_placement(&mut self) -> Option<Placement> {
+        while self.run {
+            let input = self
+                .answer(self.translation.get_message("task.placement").as_str())
+                .to_lowercase();
+
+            match input.as_str() {
+                "exit" => {
+                    self.print(
+                        self.translation.get_message("error.canceled").as_str(),
+                        Style::Error,
+                    );
+                    return None;
                 }
+                "up" => return Some(Placement::Up),
+                "down" => return Some(Placement::Down),
+                "top" => return Some(Placement::Top),
+                "bottom" => return Some(Placement::Bottom),
+                "before" => {
+                    return self
+                        .ask_id(self.translation.get_message("id.move.before").as_str())
+                        .map(Placement::Before)
+                }
+                _ if !self.run => {}
+                _ => self.fail(self.translation.get_message("error.option").as_str()),
             }
-            None => (),
         }
+
+        None
+    }
+
+    fn command_merge(&mut self) {
+        let keep = match self.ask_id(self.translation.get_message("id.merge").as_str()) {
+            Some(id) => id,
+            None => return,
+        };
+
+        let other = match self.ask_id(self.translation.get_message("id.merge.other").as_str()) {
+            Some(id) => id,
+            None => return,
+        };
+
+        self.confirm(
+            Command::Merge { keep, other },
+            "question.task.merge",
+            "success.task.merge",
+        );
     }
 
     fn command_save(&mut self) {
-        if self.modifications == true
+        if self.modifications
             && self.wanna_proceed(self.translation.get_message("question.overwrite").as_str())
-                == true
         {
-            let args = ActionArgs::new("save");
+            match self.action_manager.process(Command::Save) {
+                Ok(outcome) => {
+                    self.modifications = false;
+                    self.print(
+                        outcome_report(&outcome, &self.translation).as_str(),
+                        Style::Success,
+                    );
+                }
+                Err(error) => self.report(&error),
+            }
+        }
+    }
+
+    fn command_stats(&mut self) {
+        self.show_outcome(Command::Stats);
+    }
+
+    fn command_audit(&mut self) {
+        self.print(
+            self.translation.get_message("audit.filter").as_str(),
+            Style::Default,
+        );
+        let filter = self.read();
+
+        if !self.run {
+            return;
+        }
+
+        let entries: Vec<String> = read_entries(AUDIT_FILE)
+            .iter()
+            .filter(|entry| entry.matches(filter.as_str()))
+            .map(|entry| format!("{}\n", entry))
+            .collect();
+
+        if entries.is_empty() {
+            self.print(
+                self.translation.get_message("audit.none").as_str(),
+                Style::Warning,
+            );
+            return;
+        }
+
+        for entry in entries {
+            self.print(entry.as_str(), Style::Default);
+        }
+
+        self.print("\n", Style::Default);
+    }
+
+    /// Takes the next word typed after the command, or asks for it.
+    fn ask(&mut self, token: &'static str) -> Option<String> {
+        let input = self.answer(self.translation.get_message(token).as_str());
+
+        self.run.then_some(input)
+    }
+
+    /// Takes the next word typed after the command or, once they are all
+    /// used, shows `message` and reads an answer.
+    fn answer(&mut self, message: &str) -> String {
+        if let Some(word) = self.inline.pop_front() {
+            return word;
+        }
+
+        self.print(message, Style::Default);
+        self.read()
+    }
+
+    fn ask_format(&mut self) -> Option<Format> {
+        let message = self
+            .translation
+            .get_message("transfer.format")
+            .replace("{}", Format::NAMES);
+
+        let format = self.answer(message.as_str());
+
+        if !self.run {
+            return None;
+        }
+
+        match format.parse() {
+            Ok(format) => Some(format),
+            Err(error) => {
+                self.transfer_failed(error);
+                None
+            }
+        }
+    }
+
+    fn transfer_failed(&mut self, reason: String) {
+        let message = self
+            .translation
+            .get_message("error.transfer")
+            .replace("{}", &reason);
+
+        self.fail(message.as_str());
+    }
+
+    /// `export [format] [file] [grouped]`: writes every task to a file.
+    fn command_export(&mut self) {
+        let typed_inline = !self.inline.is_empty();
+
+        let Some(format) = self.ask_format() else {
+            return;
+        };
+        let Some(file) = self.ask("transfer.file") else {
+            return;
+        };
+
+        // Typed on the command line, grouping has to be asked for.
+        let grouped = if !format.has_groups() {
+            false
+        } else if typed_inline {
+            self.inline
+                .pop_front()
+                .is_some_and(|word| word.eq_ignore_ascii_case("grouped"))
+        } else {
+            match self.ask("transfer.groups") {
+                Some(answer) => answer == "yes",
+                None => return,
+            }
+        };
+
+        let groups = grouped.then(|| Groups {
+            open: self.translation.get_message("export.open"),
+            done: self.translation.get_message("export.done"),
+        });
+
+        let tasks = self.action_manager.tasks();
+
+        let written = transfer::export(format, &tasks, groups.as_ref())
+            .and_then(|data| fs::write(&file, data).map_err(|error| error.to_string()));
+
+        match written {
+            Ok(()) => {
+                let message = self
+                    .translation
+                    .get_message("success.export")
+                    .replace("{}", &tasks.len().to_string());
+
+                self.print(message.as_str(), Style::Success);
+            }
+            Err(reason) => self.transfer_failed(format!("{}: {}", file, reason)),
+        }
+    }
 
-            self.action_manager.process(args, &*self.display);
+    /// `import [format] [file] [columns]`: adds the tasks of a file, with
+    /// new ids. Rows that can't be read are reported and left out.
+    fn command_import(&mut self) {
+        let typed_inline = !self.inline.is_empty();
 
-            self.modifications = false;
+        let Some(format) = self.ask_format() else {
+            return;
+        };
+        let Some(file) = self.ask("transfer.file") else {
+            return;
+        };
+
+        // Typed on the command line, the rest of it is the columns.
+        let columns = if !format.has_columns() {
+            String::new()
+        } else if typed_inline {
+            self.inline.drain(..).collect::<Vec<_>>().join(" ")
+        } else {
+            match self.ask("transfer.columns") {
+                Some(columns) => columns,
+                None => return,
+            }
+        };
+
+        let imported = fs::read_to_string(&file)
+            .map_err(|error| error.to_string())
+            .and_then(|data| transfer::import(format, &data, &columns));
+
+        let imported = match imported {
+            Ok(imported) => imported,
+            Err(reason) => {
+                self.transfer_failed(format!("{}: {}", file, reason));
+                return;
+            }
+        };
+
+        for error in &imported.errors {
+            let message = self
+                .translation
+                .get_message("import.skipped")
+                .replace("{}", &error.to_string());
+
+            self.print(message.as_str(), Style::Warning);
         }
+
+        if imported.tasks.is_empty() {
+            self.fail(self.translation.get_message("import.none").as_str());
+            return;
+        }
+
+        self.confirm(
+            Command::Import {
+                tasks: imported.tasks,
+            },
+            "question.import",
+            "success.import",
+        );
     }
 
     fn command_exit(&mut self) {
-        if self.modifications == false {
-            self.run = false;
-        } else if self.wanna_proceed(
-            self.translation
-                .get_message("question.modification")
-                .as_str(),
-        ) == true
+        if !self.modifications
+            || self.wanna_proceed(
+                self.translation
+                    .get_message("question.modification")
+                    .as_str(),
+            )
         {
             self.run = false;
         }
@@ -324,6 +902,7 @@ impl Prompt {
     fn print(&mut self, message: &str, style: Style) {
         let message = match style {
             Style::Error => message.red(),
+            Style::Warning => message.yellow(),
             Style::Success => message.green(),
             Style::Fancy => message.cyan(),
             Style::Default => message.white(),
@@ -332,3 +911,186 @@ impl Prompt {
         self.display.show(message.to_string());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    use todo::{
+        action_manager::ActionManger, display::DisplayMessage, error::LoadError, reader::Reader,
+        repository::Repository, task::Task,
+    };
+
+    use super::Prompt;
+    use crate::json_repository::restore_backup;
+
+    /// Starts with `loaded` and remembers the names of the tasks of every
+    /// save.
+    #[derive(Clone, Default)]
+    struct Memory {
+        loaded: Vec<Task>,
+        saves: Arc<Mutex<Vec<Vec<String>>>>,
+    }
+
+    impl Repository for Memory {
+        fn save(&mut self, tasks: Vec<&Task>) -> bool {
+            let names = tasks.iter().map(|task| task.name.clone()).collect();
+            self.saves.lock().unwrap().push(names);
+            true
+        }
+
+        fn load(&mut self) -> Result<Vec<Task>, LoadError> {
+            Ok(self.loaded.clone())
+        }
+    }
+
+    struct Lines(VecDeque<String>);
+
+    impl Reader for Lines {
+        fn read(&mut self) -> Option<String> {
+            self.0.pop_front()
+        }
+    }
+
+    struct Quiet;
+
+    impl DisplayMessage for Quiet {
+        fn show(&self, _message: String) {}
+    }
+
+    /// Keeps everything shown, to check the messages.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<String>>);
+
+    impl DisplayMessage for Captured {
+        fn show(&self, message: String) {
+            self.0.lock().unwrap().push_str(&message);
+        }
+    }
+
+    fn run_script(lines: &[&str], keep_going: bool) -> (bool, Vec<Vec<String>>) {
+        run_script_on(Vec::new(), lines, keep_going)
+    }
+
+    fn run_script_on(
+        loaded: Vec<Task>,
+        lines: &[&str],
+        keep_going: bool,
+    ) -> (bool, Vec<Vec<String>>) {
+        let memory = Memory {
+            loaded,
+            ..Default::default()
+        };
+
+        let mut prompt = Prompt::new(
+            None,
+            Box::new(Quiet),
+            Box::new(Lines(lines.iter().map(|line| line.to_string()).collect())),
+            ActionManger::new(Box::new(memory.clone())),
+        )
+        .with_script(keep_going);
+
+        let succeeded = prompt.run();
+        let saves = memory.saves.lock().unwrap().clone();
+
+        (succeeded, saves)
+    }
+
+    #[test]
+    fn test_script_skips_blank_command_lines() {
+        let (succeeded, saves) = run_script(
+            &[
+                "add", "Milk", "Buy it", "yes", "", "  ", "save", "yes", "exit",
+            ],
+            false,
+        );
+
+        assert!(succeeded);
+        assert_eq!(saves, vec![vec!["Milk".to_string()]]);
+    }
+
+    #[test]
+    fn test_script_stops_on_first_failure() {
+        let lines = ["bogus", "add", "Milk", "Buy it", "yes", "save", "yes"];
+
+        let (succeeded, saves) = run_script(&lines, false);
+        assert!(!succeeded);
+        assert!(saves.is_empty());
+
+        let (succeeded, saves) = run_script(&lines, true);
+        assert!(!succeeded);
+        assert_eq!(saves.len(), 1);
+    }
+
+    #[test]
+    fn test_script_moves_on_one_line() {
+        let loaded = (1..=3)
+            .map(|id| Task {
+                id,
+                name: format!("Task {}", id),
+                description: "Description".to_string(),
+                ..Default::default()
+            })
+            .collect();
+
+        let (succeeded, saves) = run_script_on(
+            loaded,
+            &[
+                "move 2 top",
+                "yes",
+                "move 3 before 1",
+                "yes",
+                "save",
+                "yes",
+                "exit",
+            ],
+            false,
+        );
+
+        assert!(succeeded);
+        assert_eq!(saves, vec![vec!["Task 2", "Task 3", "Task 1"]]);
+    }
+
+    fn run_restore(lines: &[&str]) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+        let captured = Captured::default();
+
+        let mut action_manager = ActionManger::new(Box::new(Memory::default()));
+        action_manager
+            .register(restore_backup(path.to_str().unwrap()))
+            .unwrap();
+
+        let mut prompt = Prompt::new(
+            None,
+            Box::new(captured.clone()),
+            Box::new(Lines(lines.iter().map(|line| line.to_string()).collect())),
+            action_manager,
+        )
+        .with_script(false);
+
+        assert!(!prompt.run());
+
+        let output = captured.0.lock().unwrap().clone();
+        output
+    }
+
+    #[test]
+    fn test_failed_restore_is_a_read_error() {
+        let output = run_restore(&["restore-backup", "2", "exit"]);
+
+        assert!(output.contains("Type the backup number (1 is the newest): "));
+        assert!(output.contains("Couldn't read "));
+        assert!(output.contains("tasks.json.2"));
+        assert!(!output.contains("Couldn't save"));
+
+        // Typed on the same line, the number isn't asked for.
+        let output = run_restore(&["restore-backup 2", "exit"]);
+
+        assert!(!output.contains("Type the backup number"));
+        assert!(output.contains("tasks.json.2"));
+    }
+}
@@ -0,0 +1,172 @@
+use chrono::Duration;
+use todo::{
+    diff::{Change, Diff},
+    outcome::Outcome,
+    statistics::Statistics,
+    task::Task,
+};
+
+use crate::translation::Translation;
+
+const BAR_WIDTH: usize = 30;
+const WEEKS_SHOWN: usize = 8;
+
+pub fn outcome_report(outcome: &Outcome, translation: &Translation) -> String {
+    match outcome {
+        Outcome::Done => String::new(),
+        Outcome::Added { task, .. } | Outcome::Task(task) => task_line(task),
+        Outcome::Tasks(tasks) => tasks.iter().map(task_line).collect(),
+        Outcome::Statistics(statistics) => stats_report(statistics, translation),
+        Outcome::Saved(saved) => translation
+            .get_message("success.save")
+            .replace("{}", &saved.to_string()),
+    }
+}
+
+pub fn task_line(task: &Task) -> String {
+    let priority = task
+        .priority
+        .map(|priority| format!("({}) ", priority))
+        .unwrap_or_default();
+
+    let tags: String = task
+        .projects
+        .iter()
+        .map(|project| format!(" +{}", project))
+        .chain(task.contexts.iter().map(|context| format!(" @{}", context)))
+        .chain(
+            // Values with spaces or colons, such as kept iCalendar
+            // properties, are too long to show here.
+            task.attributes
+                .iter()
+                .filter(|(_, value)| !value.contains(|c: char| c.is_whitespace() || c == ':'))
+                .map(|(key, value)| format!(" {}:{}", key, value)),
+        )
+        .collect();
+
+    format!(
+        "{}. [{}] - {}{} - {}{}\n",
+        task.id,
+        if task.done { 'X' } else { ' ' },
+        priority,
+        task.name,
+        task.description,
+        tags
+    )
+}
+
+pub fn diff_report(diff: &Diff) -> String {
+    diff.changes
+        .iter()
+        .map(|change| match change {
+            Change::Added(task) => format!("+ {}", task_line(task)),
+            Change::Removed(task) => format!("- {}", task_line(task)),
+            Change::Updated { before, after } => {
+                format!("~ {}  {}", task_line(before), task_line(after))
+            }
+            Change::Moved { task, from, to } => {
+                format!("# {} -> {}: {}", from, to, task_line(task))
+            }
+        })
+        .collect()
+}
+
+pub fn stats_report(statistics: &Statistics, translation: &Translation) -> String {
+    let mut report = translation.get_message("stats.title");
+
+    report += &format!(
+        "{}{}\n{}{}\n{}{}\n",
+        translation.get_message("stats.total"),
+        statistics.total,
+        translation.get_message("stats.open"),
+        statistics.open,
+        translation.get_message("stats.done"),
+        statistics.done,
+    );
+
+    report += &format!(
+        "{}[{}] {:.0}%\n\n",
+        translation.get_message("stats.rate"),
+        bar(statistics.completion_rate, BAR_WIDTH),
+        statistics.completion_rate * 100.0
+    );
+
+    report += &translation.get_message("stats.weekly");
+
+    let weeks = &statistics.weekly[statistics.weekly.len().saturating_sub(WEEKS_SHOWN)..];
+    let highest = weeks
+        .iter()
+        .map(|week| week.created.max(week.completed))
+        .max()
+        .unwrap_or(0);
+
+    if weeks.is_empty() {
+        report += &translation.get_message("stats.none");
+    }
+
+    for week in weeks {
+        report += &format!(
+            "{}-W{:02} {}{} {}\n         {}{} {}\n",
+            week.year,
+            week.week,
+            translation.get_message("stats.created"),
+            bar_of(week.created, highest),
+            week.created,
+            translation.get_message("stats.completed"),
+            bar_of(week.completed, highest),
+            week.completed
+        );
+    }
+
+    report += &format!(
+        "\n{}{}\n\n",
+        translation.get_message("stats.average"),
+        match statistics.average_time_to_complete {
+            Some(average) => humanize(average),
+            None => "-".to_string(),
+        }
+    );
+
+    report += &translation.get_message("stats.oldest");
+
+    if statistics.oldest_open.is_empty() {
+        report += &translation.get_message("stats.none");
+    }
+
+    for task in &statistics.oldest_open {
+        let age = match task.created_at {
+            Some(created_at) => humanize(statistics.generated_at - created_at),
+            None => "-".to_string(),
+        };
+
+        report += &format!("{}. {} ({})\n", task.id, task.name, age);
+    }
+
+    report + "\n"
+}
+
+fn bar_of(value: usize, highest: usize) -> String {
+    if highest == 0 {
+        return bar(0.0, BAR_WIDTH);
+    }
+
+    bar(value as f64 / highest as f64, BAR_WIDTH)
+}
+
+fn bar(ratio: f64, width: usize) -> String {
+    let filled = (ratio.clamp(0.0, 1.0) * width as f64).round() as usize;
+
+    format!("{}{}", "#".repeat(filled), "-".repeat(width - filled))
+}
+
+fn humanize(duration: Duration) -> String {
+    let days = duration.num_days();
+    let hours = duration.num_hours() - days * 24;
+    let minutes = duration.num_minutes() - duration.num_hours() * 60;
+
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
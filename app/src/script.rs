@@ -0,0 +1,58 @@
+use std::{collections::VecDeque, fs, io};
+
+use todo::reader::Reader;
+
+/// Reads the prompt input from a file, one command or answer per line.
+/// Lines starting with `#` are comments.
+pub struct Script {
+    lines: VecDeque<String>,
+}
+
+impl Script {
+    pub fn open(path: &str) -> io::Result<Self> {
+        let lines = fs::read_to_string(path)?
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .map(|line| line.to_string())
+            .collect();
+
+        Ok(Self { lines })
+    }
+}
+
+impl Reader for Script {
+    fn read(&mut self) -> Option<String> {
+        self.lines.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+    use todo::reader::Reader;
+
+    use super::Script;
+
+    #[test]
+    fn test_reads_lines_in_order_without_comments() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            "# Seed the list\nadd\nMilk\n\n  # answers follow\nyes\n"
+        )
+        .unwrap();
+
+        let mut script = Script::open(file.path().to_str().unwrap()).unwrap();
+
+        let lines: Vec<String> = std::iter::from_fn(|| script.read()).collect();
+        assert_eq!(lines, vec!["add", "Milk", "", "yes"]);
+        assert_eq!(script.read(), None);
+    }
+
+    #[test]
+    fn test_missing_file() {
+        assert!(Script::open("there/is/no/such/script.txt").is_err());
+    }
+}
@@ -0,0 +1,236 @@
+use std::{collections::HashMap, fs, io::ErrorKind};
+
+use serde::Deserialize;
+use todo::{action_args::ActionArgsBuilder, command::Command, error::ActionError};
+
+pub const SHORTCUTS_FILE: &str = "shortcuts.json";
+
+/// Aliases and macros defined by the user, for example:
+///
+/// ```json
+/// {
+///     "aliases": { "a": "add", "c": "complete" },
+///     "macros": {
+///         "finish": {
+///             "help": "Completes a task and sends it to the bottom",
+///             "params": ["id"],
+///             "steps": [["complete", "$id"], ["move", "$id", "bottom"]]
+///         }
+///     }
+/// }
+/// ```
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Shortcuts {
+    pub aliases: HashMap<String, String>,
+    pub macros: HashMap<String, Macro>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Macro {
+    pub help: String,
+    pub params: Vec<String>,
+    pub steps: Vec<Vec<String>>,
+}
+
+impl Shortcuts {
+    /// Reads the shortcuts from a file. A file that doesn't exist means
+    /// there are none.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let data = match fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(format!("Couldn't read {}: {}", path, error)),
+        };
+
+        let shortcuts: Shortcuts =
+            serde_json::from_str(&data).map_err(|error| format!("Invalid {}: {}", path, error))?;
+
+        // Names are matched after the input is lowercased.
+        Ok(Self {
+            aliases: shortcuts
+                .aliases
+                .into_iter()
+                .map(|(alias, command)| (alias.to_lowercase(), command.to_lowercase()))
+                .collect(),
+            macros: shortcuts
+                .macros
+                .into_iter()
+                .map(|(name, steps)| (name.to_lowercase(), steps))
+                .collect(),
+        })
+    }
+
+    /// Returns the command an alias stands for, or the input itself.
+    pub fn resolve<'a>(&'a self, command: &'a str) -> &'a str {
+        self.aliases
+            .get(command)
+            .map(|command| command.as_str())
+            .unwrap_or(command)
+    }
+}
+
+impl Macro {
+    /// Replaces every `$param` in the steps with its value and reads each
+    /// step as a command.
+    pub fn expand(&self, values: &[String]) -> Result<Vec<Command>, ActionError> {
+        let mut params: Vec<(&String, &String)> = self.params.iter().zip(values).collect();
+
+        // Longer names first, so `$id` doesn't replace the start of `$idea`.
+        params.sort_by_key(|(param, _)| std::cmp::Reverse(param.len()));
+
+        self.steps
+            .iter()
+            .map(|step| {
+                let mut fields = step.iter().map(|field| substitute(field, &params));
+
+                let mut builder = ActionArgsBuilder::new();
+
+                if let Some(command) = fields.next() {
+                    builder = builder.with_command(&command);
+                }
+                if let Some(first) = fields.next() {
+                    builder = builder.with_first(first);
+                }
+                if let Some(second) = fields.next() {
+                    builder = builder.with_second(second);
+                }
+                if let Some(third) = fields.next() {
+                    builder = builder.with_third(third);
+                }
+
+                if fields.next().is_some() {
+                    return Err(ActionError::Validation {
+                        field: "macro",
+                        reason: "too many arguments",
+                    });
+                }
+
+                Command::try_from(builder.build())
+            })
+            .collect()
+    }
+}
+
+/// Replaces the `$param`s of `field` in one pass over it, so a value that
+/// contains `$other` is kept as typed.
+fn substitute(field: &str, params: &[(&String, &String)]) -> String {
+    let mut result = String::with_capacity(field.len());
+    let mut rest = field;
+
+    while let Some(at) = rest.find('$') {
+        result.push_str(&rest[..at]);
+        rest = &rest[at + 1..];
+
+        match params
+            .iter()
+            .find(|(param, _)| rest.starts_with(param.as_str()))
+        {
+            Some((param, value)) => {
+                result.push_str(value);
+                rest = &rest[param.len()..];
+            }
+            None => result.push('$'),
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+    use todo::{command::Command, task_manager::Placement};
+
+    use super::{Macro, Shortcuts};
+
+    fn finish() -> Macro {
+        Macro {
+            help: String::new(),
+            params: vec!["id".to_string(), "idea".to_string()],
+            steps: vec![
+                vec!["complete".to_string(), "$id".to_string()],
+                vec![
+                    "add".to_string(),
+                    "$idea".to_string(),
+                    "From $id".to_string(),
+                ],
+                vec!["move".to_string(), "$id".to_string(), "bottom".to_string()],
+            ],
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        let commands = finish()
+            .expand(&["3".to_string(), "Next step".to_string()])
+            .unwrap();
+
+        assert_eq!(
+            commands,
+            vec![
+                Command::Complete { id: 3 },
+                Command::Add {
+                    name: "Next step".to_string(),
+                    description: "From 3".to_string(),
+                },
+                Command::Move {
+                    id: 3,
+                    placement: Placement::Bottom,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand_keeps_values_as_typed() {
+        let commands = finish()
+            .expand(&["3".to_string(), "Pay $id and $5".to_string()])
+            .unwrap();
+
+        assert_eq!(
+            commands[1],
+            Command::Add {
+                name: "Pay $id and $5".to_string(),
+                description: "From 3".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_expand_errors() {
+        let mut long = finish();
+        long.steps = vec![vec!["add".to_string(); 5]];
+        assert!(long.expand(&[]).is_err());
+
+        // A missing value leaves `$id` in place, which isn't an id.
+        assert!(finish().expand(&[]).is_err());
+    }
+
+    #[test]
+    fn test_load_lowercases_names() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"{{"aliases": {{"A": "Add"}}, "macros": {{"Finish": {{"params": ["id"]}}}}}}"#
+        )
+        .unwrap();
+
+        let shortcuts = Shortcuts::load(file.path().to_str().unwrap()).unwrap();
+        assert_eq!(shortcuts.resolve("a"), "add");
+        assert_eq!(shortcuts.resolve("remove"), "remove");
+        assert!(shortcuts.macros.contains_key("finish"));
+
+        assert!(Shortcuts::load("there/is/no/such/file.json")
+            .unwrap()
+            .aliases
+            .is_empty());
+
+        write!(file, "not json").unwrap();
+        assert!(Shortcuts::load(file.path().to_str().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,376 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use rusqlite::{params, types::Type, Connection, Row};
+use todo::{error::LoadError, repository::Repository, task::Task};
+
+/// Schema changes, in order. A database records how many it has applied
+/// in `user_version`, so only the new ones run when it is opened. Never
+/// edit one that was released: add another.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE tasks (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        description TEXT NOT NULL,
+        done INTEGER NOT NULL DEFAULT 0,
+        position INTEGER NOT NULL DEFAULT 0,
+        created_at TEXT,
+        completed_at TEXT
+    );",
+    "CREATE INDEX tasks_position ON tasks (position);",
+    "ALTER TABLE tasks ADD COLUMN priority TEXT;
+     ALTER TABLE tasks ADD COLUMN projects TEXT NOT NULL DEFAULT '';
+     ALTER TABLE tasks ADD COLUMN contexts TEXT NOT NULL DEFAULT '';
+     ALTER TABLE tasks ADD COLUMN attributes TEXT NOT NULL DEFAULT '{}';",
+];
+
+/// Keeps the tasks in a SQLite database. Saving only writes the tasks that
+/// changed since the last load or save.
+pub struct SqliteRepository {
+    connection: Connection,
+    /// What the database holds, as far as this repository knows.
+    stored: HashMap<u32, Task>,
+    /// Set when some rows couldn't be read: the next save replaces them
+    /// all, since only the readable ones are known.
+    rewrite: bool,
+}
+
+impl SqliteRepository {
+    /// Opens the database, creating it if needed, and brings its schema up
+    /// to date.
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        let mut connection = Connection::open(path)?;
+
+        migrate(&mut connection)?;
+
+        Ok(Self {
+            connection,
+            stored: HashMap::new(),
+            rewrite: false,
+        })
+    }
+
+    fn write(&mut self, tasks: &[&Task]) -> rusqlite::Result<()> {
+        let unknown = HashMap::new();
+        let stored = if self.rewrite { &unknown } else { &self.stored };
+
+        let transaction = self.connection.transaction()?;
+
+        if self.rewrite {
+            transaction.execute("DELETE FROM tasks", [])?;
+        }
+
+        {
+            let mut delete = transaction.prepare("DELETE FROM tasks WHERE id = ?1")?;
+
+            let current: HashSet<u32> = tasks.iter().map(|task| task.id).collect();
+
+            for id in stored.keys() {
+                if !current.contains(id) {
+                    delete.execute([id])?;
+                }
+            }
+
+            let mut upsert = transaction.prepare(
+                "INSERT INTO tasks (id, name, description, done, position, created_at, completed_at,
+                                    priority, projects, contexts, attributes)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+                 ON CONFLICT (id) DO UPDATE SET
+                    name = excluded.name,
+                    description = excluded.description,
+                    done = excluded.done,
+                    position = excluded.position,
+                    created_at = excluded.created_at,
+                    completed_at = excluded.completed_at,
+                    priority = excluded.priority,
+                    projects = excluded.projects,
+                    contexts = excluded.contexts,
+                    attributes = excluded.attributes",
+            )?;
+
+            for task in tasks {
+                if stored.get(&task.id) == Some(*task) {
+                    continue;
+                }
+
+                upsert.execute(params![
+                    task.id,
+                    task.name,
+                    task.description,
+                    task.done,
+                    task.position,
+                    task.created_at,
+                    task.completed_at,
+                    task.priority.map(String::from),
+                    task.projects.join(" "),
+                    task.contexts.join(" "),
+                    serde_json::to_string(&task.attributes).unwrap_or_default(),
+                ])?;
+            }
+        }
+
+        transaction.commit()?;
+        self.rewrite = false;
+
+        Ok(())
+    }
+
+    /// Reads every row, keeping the ones that are valid apart from the
+    /// error of the first one that isn't.
+    fn read(&self) -> rusqlite::Result<(Vec<Task>, Option<rusqlite::Error>)> {
+        let mut select = self.connection.prepare(
+            "SELECT id, name, description, done, position, created_at, completed_at,
+                    priority, projects, contexts, attributes
+             FROM tasks ORDER BY position, id",
+        )?;
+
+        let mut tasks = Vec::new();
+        let mut invalid = None;
+
+        for row in select.query_map([], task_from_row)? {
+            match row {
+                Ok(task) => tasks.push(task),
+                Err(error) => {
+                    invalid.get_or_insert(error);
+                }
+            }
+        }
+
+        Ok((tasks, invalid))
+    }
+}
+
+impl Repository for SqliteRepository {
+    fn save(&mut self, tasks: Vec<&Task>) -> bool {
+        if self.write(&tasks).is_err() {
+            return false;
+        }
+
+        self.stored = tasks
+            .into_iter()
+            .map(|task| (task.id, task.clone()))
+            .collect();
+
+        true
+    }
+
+    fn load(&mut self) -> Result<Vec<Task>, LoadError> {
+        let (tasks, invalid) = self
+            .read()
+            .map_err(|error| LoadError::Unreadable(error.to_string()))?;
+
+        self.stored = tasks.iter().map(|task| (task.id, task.clone())).collect();
+
+        match invalid {
+            None => Ok(tasks),
+            Some(error) => {
+                self.rewrite = true;
+
+                Err(LoadError::Corrupt {
+                    reason: error.to_string(),
+                    salvaged: tasks,
+                })
+            }
+        }
+    }
+}
+
+fn migrate(connection: &mut Connection) -> rusqlite::Result<()> {
+    let applied: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    let transaction = connection.transaction()?;
+
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", version + 1)?;
+    }
+
+    transaction.commit()
+}
+
+fn task_from_row(row: &Row) -> rusqlite::Result<Task> {
+    Ok(Task {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        done: row.get(3)?,
+        position: row.get(4)?,
+        created_at: row.get(5)?,
+        completed_at: row.get(6)?,
+        priority: row
+            .get::<_, Option<String>>(7)?
+            .and_then(|priority| priority.chars().next()),
+        projects: words(row.get(8)?),
+        contexts: words(row.get(9)?),
+        attributes: attributes(row.get(10)?)?,
+    })
+}
+
+/// Tags are kept as one space separated column, since they have no
+/// spaces themselves.
+fn words(column: String) -> Vec<String> {
+    column.split_whitespace().map(String::from).collect()
+}
+
+fn attributes(column: String) -> rusqlite::Result<BTreeMap<String, String>> {
+    serde_json::from_str(&column)
+        .map_err(|error| rusqlite::Error::FromSqlConversionFailure(10, Type::Text, Box::new(error)))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use rusqlite::Connection;
+    use tempfile::TempDir;
+    use todo::{error::LoadError, repository::Repository, task::Task};
+
+    use super::{SqliteRepository, MIGRATIONS};
+
+    fn task(id: u32, name: &str) -> Task {
+        Task {
+            id,
+            name: name.to_string(),
+            description: format!("About {}", name),
+            position: id as u64 * 1024,
+            created_at: Some(Utc.with_ymd_and_hms(2024, 1, id, 9, 0, 0).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    fn database() -> (TempDir, String) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("tasks.db").to_str().unwrap().to_string();
+
+        (dir, path)
+    }
+
+    fn names(connection: &Connection) -> Vec<String> {
+        let mut select = connection
+            .prepare("SELECT name FROM tasks ORDER BY position")
+            .unwrap();
+
+        select
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (_dir, path) = database();
+
+        let mut full = task(1, "Full");
+        full.done = true;
+        full.completed_at = Some(Utc.with_ymd_and_hms(2024, 2, 1, 10, 30, 0).unwrap());
+        full.priority = Some('B');
+        full.projects = vec!["home".to_string(), "garden".to_string()];
+        full.contexts = vec!["phone".to_string()];
+        full.attributes
+            .insert("due".to_string(), "2024-03-01".to_string());
+        let plain = task(2, "Plain");
+
+        let mut repository = SqliteRepository::open(&path).unwrap();
+        assert!(repository.save(vec![&full, &plain]));
+
+        let loaded = SqliteRepository::open(&path).unwrap().load().unwrap();
+        assert_eq!(loaded, vec![full, plain]);
+    }
+
+    #[test]
+    fn test_migrates_older_databases() {
+        let (_dir, path) = database();
+
+        {
+            let connection = Connection::open(&path).unwrap();
+            connection.execute_batch(MIGRATIONS[0]).unwrap();
+            connection.execute_batch(MIGRATIONS[1]).unwrap();
+            connection.pragma_update(None, "user_version", 2).unwrap();
+            connection
+                .execute(
+                    "INSERT INTO tasks (id, name, description, done, position)
+                     VALUES (7, 'Old', 'Saved before tags', 0, 1024)",
+                    [],
+                )
+                .unwrap();
+        }
+
+        let loaded = SqliteRepository::open(&path).unwrap().load().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "Old");
+        assert_eq!(loaded[0].priority, None);
+        assert!(loaded[0].projects.is_empty() && loaded[0].attributes.is_empty());
+
+        let version: usize = Connection::open(&path)
+            .unwrap()
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+
+        // Opening it again runs nothing twice.
+        assert!(SqliteRepository::open(&path).is_ok());
+    }
+
+    #[test]
+    fn test_only_changed_rows_are_written() {
+        let (_dir, path) = database();
+        let first = task(1, "First");
+        let mut second = task(2, "Second");
+        let third = task(3, "Third");
+
+        let mut repository = SqliteRepository::open(&path).unwrap();
+        assert!(repository.save(vec![&first, &second, &third]));
+
+        // Changed behind the repository's back: a row it rewrites loses
+        // this, one it leaves alone keeps it.
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute("UPDATE tasks SET name = 'Edited' WHERE id IN (1, 2)", [])
+            .unwrap();
+
+        second.name = "Second again".to_string();
+        assert!(repository.save(vec![&first, &second]));
+
+        assert_eq!(names(&connection), vec!["Edited", "Second again"]);
+
+        // After a load, unchanged tasks are still left alone.
+        let mut repository = SqliteRepository::open(&path).unwrap();
+        let loaded = repository.load().unwrap();
+        connection
+            .execute("UPDATE tasks SET description = 'Kept' WHERE id = 1", [])
+            .unwrap();
+        assert!(repository.save(loaded.iter().collect()));
+
+        let description: String = connection
+            .query_row("SELECT description FROM tasks WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(description, "Kept");
+    }
+
+    #[test]
+    fn test_invalid_rows() {
+        let (_dir, path) = database();
+        let first = task(1, "First");
+        let second = task(2, "Second");
+
+        let mut repository = SqliteRepository::open(&path).unwrap();
+        assert!(repository.save(vec![&first, &second]));
+
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute("UPDATE tasks SET attributes = 'not json' WHERE id = 2", [])
+            .unwrap();
+
+        let mut repository = SqliteRepository::open(&path).unwrap();
+        let salvaged = match repository.load() {
+            Err(LoadError::Corrupt { salvaged, .. }) => salvaged,
+            result => panic!("unexpected result {:?}", result),
+        };
+        assert_eq!(salvaged, vec![first]);
+
+        // The next save replaces every row, the unreadable one included.
+        assert!(repository.save(salvaged.iter().collect()));
+        assert_eq!(names(&connection), vec!["First"]);
+    }
+}
@@ -1,32 +1,71 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use todo::task::Task;
 
-#[derive(Serialize, Deserialize)]
-pub struct TaskMapper {
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TaskMapper<'a> {
     pub id: u32,
-    pub name: String,
-    pub description: String,
+    pub name: Cow<'a, str>,
+    pub description: Cow<'a, str>,
     pub done: bool,
+    #[serde(default)]
+    pub position: u64,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<char>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub projects: Vec<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contexts: Vec<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
 }
 
-impl From<Task> for TaskMapper {
-    fn from(value: Task) -> Self {
+impl<'a> From<&'a Task> for TaskMapper<'a> {
+    fn from(value: &'a Task) -> Self {
         Self {
             id: value.id,
-            name: value.name,
-            description: value.description,
+            name: Cow::Borrowed(&value.name),
+            description: Cow::Borrowed(&value.description),
             done: value.done,
+            position: value.position,
+            created_at: value.created_at,
+            completed_at: value.completed_at,
+            priority: value.priority,
+            projects: value
+                .projects
+                .iter()
+                .map(|tag| Cow::Borrowed(tag.as_str()))
+                .collect(),
+            contexts: value
+                .contexts
+                .iter()
+                .map(|tag| Cow::Borrowed(tag.as_str()))
+                .collect(),
+            attributes: value.attributes.clone(),
         }
     }
 }
 
-impl Into<Task> for TaskMapper {
-    fn into(self) -> Task {
-        Task {
-            id: self.id,
-            name: self.name,
-            description: self.description,
-            done: self.done,
+impl From<TaskMapper<'_>> for Task {
+    fn from(value: TaskMapper) -> Self {
+        Self {
+            id: value.id,
+            name: value.name.into_owned(),
+            description: value.description.into_owned(),
+            done: value.done,
+            position: value.position,
+            created_at: value.created_at,
+            completed_at: value.completed_at,
+            priority: value.priority,
+            projects: value.projects.into_iter().map(Cow::into_owned).collect(),
+            contexts: value.contexts.into_iter().map(Cow::into_owned).collect(),
+            attributes: value.attributes,
         }
     }
 }
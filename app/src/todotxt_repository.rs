@@ -0,0 +1,474 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, ErrorKind},
+};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use todo::{error::LoadError, repository::Repository, task::Task};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Separates the name from the description in the text of a line, when
+/// they differ, and ends text whose last word would read as a tag.
+const DESCRIPTION_SEPARATOR: &str = "--";
+
+/// Keeps the tasks in a todo.txt file, one per line:
+///
+/// `x (A) 2024-01-02 2024-01-01 name -- description +project @context key:value`
+///
+/// todo.txt has no ids, so tasks are numbered by line when loaded, and its
+/// dates have no time, so times are read as midnight UTC. Done tasks keep
+/// their priority as `pri:A`, as todo.txt tools do. A task whose name
+/// starts like a date or a priority is written with dates, made up if it
+/// has none, so its name reads back whole. Projects, contexts and
+/// attributes are only read from the words after the text, so words such
+/// as `10:30` or `@home` inside it stay text.
+pub struct TodoTxtRepository {
+    path: String,
+}
+
+impl TodoTxtRepository {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+        }
+    }
+
+    fn write(&self, tasks: &[&Task]) -> io::Result<()> {
+        let data: String = tasks
+            .iter()
+            .map(|task| format!("{}\n", write_line(task)))
+            .collect();
+
+        let replacement = format!("{}.tmp", self.path);
+
+        fs::write(&replacement, data)?;
+        fs::rename(replacement, &self.path)
+    }
+}
+
+impl Repository for TodoTxtRepository {
+    fn save(&mut self, tasks: Vec<&Task>) -> bool {
+        self.write(&tasks).is_ok()
+    }
+
+    fn load(&mut self) -> Result<Vec<Task>, LoadError> {
+        let data = match fs::read_to_string(&self.path) {
+            Ok(data) => data,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(LoadError::Unreadable(error.to_string())),
+        };
+
+        let mut tasks = Vec::new();
+        let mut invalid = None;
+
+        for (index, line) in data.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let number = index as u32 + 1;
+
+            match read_line(line) {
+                Some(mut task) => {
+                    task.id = number;
+                    task.position = number as u64;
+                    tasks.push(task);
+                }
+                None => {
+                    invalid.get_or_insert(number);
+                }
+            }
+        }
+
+        match invalid {
+            None => Ok(tasks),
+            Some(number) => Err(LoadError::Corrupt {
+                reason: format!("line {} has no task name", number),
+                salvaged: tasks,
+            }),
+        }
+    }
+}
+
+/// Reads a line as a task without an id, or `None` if it has no name.
+fn read_line(line: &str) -> Option<Task> {
+    let mut words = line.split_whitespace().peekable();
+
+    let done = words.next_if_eq(&"x").is_some();
+
+    let mut priority = if done {
+        None
+    } else {
+        words
+            .next_if(|word| read_priority(word).is_some())
+            .and_then(read_priority)
+    };
+
+    // A done task's first date is when it was completed, and only a done
+    // task has a second one.
+    let first = words
+        .next_if(|word| read_date(word).is_some())
+        .and_then(read_date);
+    let second = if done {
+        words
+            .next_if(|word| read_date(word).is_some())
+            .and_then(read_date)
+    } else {
+        None
+    };
+
+    let (created_at, completed_at) = if done { (second, first) } else { (first, None) };
+
+    let mut text: Vec<&str> = words.collect();
+    let tags = text.split_off(
+        text.iter()
+            .rposition(|word| !is_tag(word))
+            .map_or(0, |at| at + 1),
+    );
+
+    if text.last() == Some(&DESCRIPTION_SEPARATOR) {
+        text.pop();
+    }
+
+    let mut projects = Vec::new();
+    let mut contexts = Vec::new();
+    let mut attributes = BTreeMap::new();
+
+    for word in tags {
+        if let Some(project) = word.strip_prefix('+').filter(|tag| !tag.is_empty()) {
+            projects.push(project.to_string());
+        } else if let Some(context) = word.strip_prefix('@').filter(|tag| !tag.is_empty()) {
+            contexts.push(context.to_string());
+        } else if let Some((key, value)) = read_attribute(word) {
+            if key == "pri" && done && priority.is_none() {
+                priority = read_priority(&format!("({})", value));
+
+                if priority.is_some() {
+                    continue;
+                }
+            }
+
+            attributes.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    let (name, description) = match text.iter().position(|word| *word == DESCRIPTION_SEPARATOR) {
+        Some(at) => (text[..at].join(" "), text[at + 1..].join(" ")),
+        None => (text.join(" "), String::new()),
+    };
+
+    if name.is_empty() {
+        return None;
+    }
+
+    Some(Task {
+        id: 0,
+        description: if description.is_empty() {
+            name.clone()
+        } else {
+            description
+        },
+        name,
+        done,
+        position: 0,
+        created_at,
+        completed_at,
+        priority,
+        projects,
+        contexts,
+        attributes,
+    })
+}
+
+fn write_line(task: &Task) -> String {
+    let mut words = Vec::new();
+    let date = |at: DateTime<Utc>| at.format(DATE_FORMAT).to_string();
+
+    // A name starting with what reads as a mark, a priority or a date is
+    // only read as text after all the dates, so they are written even when
+    // the task lacks them, filled in from the other date or today.
+    let protect = is_ambiguous(&task.name);
+    let filled = || {
+        task.created_at
+            .or(task.completed_at)
+            .unwrap_or_else(Utc::now)
+    };
+
+    if task.done {
+        words.push("x".to_string());
+
+        // A creation date alone would be read as the completion date.
+        if task.completed_at.is_some() || protect {
+            words.push(date(task.completed_at.unwrap_or_else(filled)));
+        }
+
+        if (task.completed_at.is_some() && task.created_at.is_some()) || protect {
+            words.push(date(task.created_at.unwrap_or_else(filled)));
+        }
+    } else {
+        words.extend(task.priority.map(|priority| format!("({})", priority)));
+
+        if task.created_at.is_some() || protect {
+            words.push(date(task.created_at.unwrap_or_else(filled)));
+        }
+    }
+
+    words.extend(task.name.split_whitespace().map(str::to_string));
+
+    if task.description != task.name {
+        words.push(DESCRIPTION_SEPARATOR.to_string());
+        words.extend(task.description.split_whitespace().map(str::to_string));
+    }
+
+    // Otherwise the end of the text would be read back as tags.
+    if words.last().is_some_and(|word| is_tag(word)) {
+        words.push(DESCRIPTION_SEPARATOR.to_string());
+    }
+
+    words.extend(task.projects.iter().map(|project| format!("+{}", project)));
+    words.extend(task.contexts.iter().map(|context| format!("@{}", context)));
+
+    if task.done {
+        words.extend(task.priority.map(|priority| format!("pri:{}", priority)));
+    }
+
+    // Attributes that wouldn't be read back as one `key:value` word, such
+    // as a kept iCalendar property, are left out.
+    words.extend(
+        task.attributes
+            .iter()
+            .map(|(key, value)| format!("{}:{}", key, value))
+            .filter(|word| !word.contains(char::is_whitespace) && read_attribute(word).is_some()),
+    );
+
+    words.join(" ")
+}
+
+/// Whether the first word of `name` would be read as something else at
+/// the start of a line.
+fn is_ambiguous(name: &str) -> bool {
+    name.split_whitespace().next().is_some_and(|word| {
+        word == "x" || read_priority(word).is_some() || read_date(word).is_some()
+    })
+}
+
+/// Whether `word` reads as a project, a context or an attribute.
+fn is_tag(word: &str) -> bool {
+    word.strip_prefix(['+', '@'])
+        .is_some_and(|tag| !tag.is_empty())
+        || read_attribute(word).is_some()
+}
+
+/// `(A)` to `(Z)`.
+fn read_priority(word: &str) -> Option<char> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+    let mut chars = letter.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) if letter.is_ascii_uppercase() => Some(letter),
+        _ => None,
+    }
+}
+
+fn read_date(word: &str) -> Option<DateTime<Utc>> {
+    NaiveDate::parse_from_str(word, DATE_FORMAT)
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc())
+}
+
+/// `key:value`, where neither has a colon. Links such as `https://...`
+/// are left in the text.
+fn read_attribute(word: &str) -> Option<(&str, &str)> {
+    let (key, value) = word.split_once(':')?;
+
+    if key.is_empty() || value.is_empty() || value.contains(':') || value.starts_with("//") {
+        return None;
+    }
+
+    Some((key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::{DateTime, TimeZone, Utc};
+    use tempfile::TempDir;
+    use todo::{error::LoadError, repository::Repository, task::Task};
+
+    use super::{read_line, write_line, TodoTxtRepository};
+
+    fn task(name: &str) -> Task {
+        Task {
+            name: name.to_string(),
+            description: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn day(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap()
+    }
+
+    fn todo_file() -> (TempDir, String) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("todo.txt").to_str().unwrap().to_string();
+
+        (dir, path)
+    }
+
+    #[test]
+    fn test_read_line() {
+        let task = read_line(
+            "x 2024-01-03 2024-01-02 Pay rent -- before the 5th https://bank.example +home @bank due:2024-01-05 pri:B",
+        )
+        .unwrap();
+
+        assert!(task.done);
+        assert_eq!(task.completed_at, Some(day(3)));
+        assert_eq!(task.created_at, Some(day(2)));
+        assert_eq!(task.name, "Pay rent");
+        assert_eq!(task.description, "before the 5th https://bank.example");
+        assert_eq!(task.priority, Some('B'));
+        assert_eq!(task.projects, vec!["home"]);
+        assert_eq!(task.contexts, vec!["bank"]);
+        assert_eq!(task.attributes.len(), 1);
+        assert_eq!(task.attributes["due"], "2024-01-05");
+
+        let task = read_line("(A) 2024-01-02 Call mum").unwrap();
+        assert!(!task.done);
+        assert_eq!(task.priority, Some('A'));
+        assert_eq!(task.created_at, Some(day(2)));
+        assert_eq!(task.description, "Call mum");
+
+        // An open task has one date; what follows is its name.
+        let task = read_line("2024-01-02 2024-01-01 release").unwrap();
+        assert_eq!(task.created_at, Some(day(2)));
+        assert_eq!(task.name, "2024-01-01 release");
+
+        // Tags are only read after the text.
+        let task = read_line("Meet @home at 10:30 -- +work desk").unwrap();
+        assert_eq!(task.name, "Meet @home at 10:30");
+        assert_eq!(task.description, "+work desk");
+        assert!(task.contexts.is_empty() && task.projects.is_empty());
+        assert!(task.attributes.is_empty());
+
+        assert!(read_line("(A) 2024-01-02 +home @bank due:2024-01-05").is_none());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (_dir, path) = todo_file();
+
+        let mut full = task("Pay rent");
+        full.description = "Before the 5th".to_string();
+        full.done = true;
+        full.created_at = Some(day(2));
+        full.completed_at = Some(day(3));
+        full.priority = Some('B');
+        full.projects = vec!["home".to_string()];
+        full.contexts = vec!["bank".to_string()];
+        full.attributes
+            .insert("due".to_string(), "2024-01-05".to_string());
+
+        let mut open = task("Call mum");
+        open.priority = Some('A');
+        open.created_at = Some(day(1));
+
+        let mut repository = TodoTxtRepository::new(&path);
+        assert!(repository.save(vec![&full, &open, &task("Plain")]));
+
+        let loaded = repository.load().unwrap();
+
+        for (number, (loaded, saved)) in loaded.iter().zip([full, open, task("Plain")]).enumerate()
+        {
+            let number = number as u32 + 1;
+
+            assert_eq!(
+                *loaded,
+                Task {
+                    id: number,
+                    position: number as u64,
+                    ..saved
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_names_that_look_like_fields() {
+        for name in ["2024-01-01 release", "x marks the spot", "(B) plan", "x"] {
+            for done in [false, true] {
+                let mut saved = task(name);
+                saved.done = done;
+
+                let line = write_line(&saved);
+                let loaded = read_line(&line).unwrap();
+
+                assert_eq!(loaded.name, name, "read from '{}'", line);
+                assert_eq!(loaded.done, done, "read from '{}'", line);
+                assert_eq!(loaded.priority, None, "read from '{}'", line);
+                assert!(loaded.created_at.is_some());
+            }
+        }
+
+        // Dates the task has are kept, and fill in for each other.
+        let mut done = task("2024-01-01 release");
+        done.done = true;
+        done.completed_at = Some(day(9));
+
+        let loaded = read_line(&write_line(&done)).unwrap();
+        assert_eq!(loaded.completed_at, Some(day(9)));
+        assert_eq!(loaded.created_at, Some(day(9)));
+        assert_eq!(loaded.name, "2024-01-01 release");
+
+        // Other names are written without made-up dates.
+        assert_eq!(
+            write_line(&task("release 2024-01-01")),
+            "release 2024-01-01"
+        );
+    }
+
+    #[test]
+    fn test_text_that_looks_like_tags() {
+        let mut saved = task("Call @Bob +Ann at 10:30");
+        saved.description = "Desk @home phone +word".to_string();
+        saved.projects = vec!["work".to_string()];
+        saved
+            .attributes
+            .insert("due".to_string(), "2024-01-05".to_string());
+
+        let ends_with_tags = task("Ask +Ann @10:30");
+
+        for saved in [saved, ends_with_tags, task("10:30")] {
+            let line = write_line(&saved);
+            assert_eq!(read_line(&line).unwrap(), saved, "read from '{}'", line);
+        }
+    }
+
+    #[test]
+    fn test_load() {
+        let (_dir, path) = todo_file();
+        let mut repository = TodoTxtRepository::new(&path);
+
+        assert_eq!(repository.load().unwrap(), Vec::new());
+
+        fs::write(&path, "First\n\n(A) +home\nThird\n").unwrap();
+
+        match repository.load() {
+            Err(LoadError::Corrupt { reason, salvaged }) => {
+                assert_eq!(reason, "line 3 has no task name");
+
+                let numbered: Vec<(u32, &str)> = salvaged
+                    .iter()
+                    .map(|task| (task.id, task.name.as_str()))
+                    .collect();
+                assert_eq!(numbered, vec![(1, "First"), (4, "Third")]);
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
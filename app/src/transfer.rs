@@ -0,0 +1,86 @@
+use std::{fmt::Display, str::FromStr};
+
+use todo::task::Task;
+
+use crate::{csv_file, ical_file, markdown_file};
+
+/// File formats tasks can be exported to and imported from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Csv,
+    Markdown,
+    Ical,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "csv" => Ok(Format::Csv),
+            "markdown" | "md" => Ok(Format::Markdown),
+            "ical" | "ics" => Ok(Format::Ical),
+            _ => Err(format!("unknown format '{}'", name)),
+        }
+    }
+}
+
+impl Format {
+    pub const NAMES: &'static str = "csv/markdown/ical";
+
+    /// Whether the import can be told which column holds which field.
+    pub fn has_columns(&self) -> bool {
+        matches!(self, Format::Csv)
+    }
+
+    /// Whether the export can group the tasks under headings.
+    pub fn has_groups(&self) -> bool {
+        matches!(self, Format::Markdown)
+    }
+}
+
+/// Headings to export open and done tasks under, for the formats that
+/// have them.
+pub struct Groups {
+    pub open: String,
+    pub done: String,
+}
+
+/// Tasks read from a file, with the rows that couldn't be read and why.
+/// The tasks have no id yet: `TaskManager::import` gives them one.
+#[derive(Debug, Default)]
+pub struct Imported {
+    pub tasks: Vec<Task>,
+    pub errors: Vec<RowError>,
+}
+
+#[derive(Debug)]
+pub struct RowError {
+    /// Line of the file the row starts at.
+    pub line: u64,
+    pub reason: String,
+}
+
+impl Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+pub fn export(format: Format, tasks: &[Task], groups: Option<&Groups>) -> Result<String, String> {
+    match format {
+        Format::Csv => csv_file::export(tasks),
+        Format::Markdown => Ok(markdown_file::export(tasks, groups)),
+        Format::Ical => Ok(ical_file::export(tasks)),
+    }
+}
+
+/// Reads the tasks in `data`. `columns` says which column holds which
+/// field, for the formats that have columns; empty means guess.
+pub fn import(format: Format, data: &str, columns: &str) -> Result<Imported, String> {
+    match format {
+        Format::Csv => csv_file::import(data, columns),
+        Format::Markdown => Ok(markdown_file::import(data)),
+        Format::Ical => Ok(ical_file::import(data)),
+    }
+}
@@ -21,24 +21,49 @@ impl Translation {
         self.tokens.get(token).unwrap().clone()
     }
 
+    /// Like `get_message`, for tokens made up at run time that may not
+    /// exist, such as the texts of registered actions.
+    pub fn find_message(&self, token: &str) -> Option<String> {
+        self.tokens.get(token).cloned()
+    }
+
     fn load_default() -> HashMap<&'static str, String> {
         let mut tokens: HashMap<&'static str, String> = HashMap::new();
 
         tokens.insert("error.command", "Invalid command\n\n".to_owned());
         tokens.insert("error.option", "Invalid option.\n\n".to_owned());
         tokens.insert("error.canceled", "Canceled\n\n".to_owned());
-        tokens.insert("error.task.add", "Couldn't add a new Task\n\n".to_owned());
+        tokens.insert("error.not_found", "Task {} not found\n\n".to_owned());
+        tokens.insert(
+            "error.invalid_id",
+            "'{}' is not a valid task ID\n\n".to_owned(),
+        );
+        tokens.insert(
+            "error.validation.name",
+            "The name can't be empty\n\n".to_owned(),
+        );
+        tokens.insert(
+            "error.validation.description",
+            "The description can't be empty\n\n".to_owned(),
+        );
         tokens.insert(
-            "error.task.remove",
-            "Couldn't remove the Task\n\n".to_owned(),
+            "error.validation.same",
+            "Please. Choose two different Tasks\n\n".to_owned(),
         );
+        tokens.insert("error.validation", "Invalid {}\n\n".to_owned());
         tokens.insert(
-            "error.task.update",
-            "Couldn't update the Task\n\n".to_owned(),
+            "error.storage",
+            "Couldn't save the tasks: {}\n\n".to_owned(),
         );
+        tokens.insert("error.unreadable", "Couldn't read {}\n\n".to_owned());
+        tokens.insert("error.rejected", "Not allowed: {}\n\n".to_owned());
         tokens.insert(
-            "error.task.complete",
-            "Couldn't complete the Task\n\n".to_owned(),
+            "error.transaction.open",
+            "Finish the open transaction first\n\n".to_owned(),
+        );
+        tokens.insert(
+            "error.transaction.none",
+            "There is no open transaction\n\n".to_owned(),
         );
         tokens.insert(
             "error.task.id",
@@ -69,6 +94,18 @@ impl Translation {
             "question.task.complete",
             "Would you like to complete? (yes/no): ".to_owned(),
         );
+        tokens.insert(
+            "question.task.move",
+            "Would you like to move? (yes/no): ".to_owned(),
+        );
+        tokens.insert(
+            "question.task.merge",
+            "Would you like to merge? (yes/no): ".to_owned(),
+        );
+        tokens.insert(
+            "warning.task.similar",
+            "There are open tasks that look like this one:\n".to_owned(),
+        );
         tokens.insert(
             "success.task.add",
             "New Task added successfully\n\n".to_owned(),
@@ -85,6 +122,66 @@ impl Translation {
             "success.task.complete",
             "Task completed successfully\n\n".to_owned(),
         );
+        tokens.insert(
+            "success.task.move",
+            "Task moved successfully\n\n".to_owned(),
+        );
+        tokens.insert(
+            "success.task.merge",
+            "Tasks merged successfully\n\n".to_owned(),
+        );
+        tokens.insert("success.custom", "Done\n\n".to_owned());
+        tokens.insert(
+            "success.import",
+            "Tasks imported successfully\n\n".to_owned(),
+        );
+        tokens.insert("success.export", "{} tasks exported\n\n".to_owned());
+        tokens.insert("transfer.format", "Type the file format ({}): ".to_owned());
+        tokens.insert("transfer.file", "Type the file name: ".to_owned());
+        tokens.insert(
+            "transfer.groups",
+            "Group the tasks by status? (yes/no): ".to_owned(),
+        );
+        tokens.insert("export.open", "Open".to_owned());
+        tokens.insert("export.done", "Done".to_owned());
+        tokens.insert(
+            "transfer.columns",
+            "Type the columns, like Title=name, 2=description (empty to guess): ".to_owned(),
+        );
+        tokens.insert("import.skipped", "Skipped {}\n".to_owned());
+        tokens.insert("import.none", "No task could be imported\n\n".to_owned());
+        tokens.insert(
+            "error.transfer",
+            "Couldn't transfer the tasks: {}\n\n".to_owned(),
+        );
+        tokens.insert(
+            "question.import",
+            "Would you like to import them? (yes/no): ".to_owned(),
+        );
+        tokens.insert(
+            "load.unreadable",
+            "The tasks couldn't be read: {}\n".to_owned(),
+        );
+        tokens.insert("load.corrupt", "The tasks are corrupt: {}\n".to_owned());
+        tokens.insert(
+            "load.unsupported",
+            "The tasks can't be read: {}. Please update the app.\n\n".to_owned(),
+        );
+        tokens.insert(
+            "load.salvage",
+            "{} tasks can still be read from them.\n".to_owned(),
+        );
+        tokens.insert(
+            "load.question",
+            "Start empty, restore a backup or keep what can be read? ({}): ".to_owned(),
+        );
+        tokens.insert(
+            "success.load",
+            "{} tasks loaded. Save to keep them.\n\n".to_owned(),
+        );
+        tokens.insert("success.save", "{} tasks saved\n\n".to_owned());
+        tokens.insert("success.macro", "Macro run successfully\n\n".to_owned());
+        tokens.insert("macro.param", "Type the {}: ".to_owned());
         tokens.insert(
             "id.remove",
             "Type the task id to delete or exit to cancel: ".to_owned(),
@@ -97,17 +194,78 @@ impl Translation {
             "id.complete",
             "Type the task id to complete or exit to cancel: ".to_owned(),
         );
+        tokens.insert(
+            "id.move",
+            "Type the task id to move or exit to cancel: ".to_owned(),
+        );
+        tokens.insert(
+            "id.move.before",
+            "Type the task id to move before or exit to cancel: ".to_owned(),
+        );
+        tokens.insert(
+            "id.merge",
+            "Type the id of the task to keep or exit to cancel: ".to_owned(),
+        );
+        tokens.insert(
+            "id.merge.other",
+            "Type the id of the task to merge into it or exit to cancel: ".to_owned(),
+        );
+        tokens.insert("preview.title", "This will change:\n".to_owned());
+        tokens.insert("preview.none", "Nothing\n".to_owned());
+        tokens.insert(
+            "task.placement",
+            "Where to move it? (up/down/top/bottom/before): ".to_owned(),
+        );
         tokens.insert("task.name", "Type the task name: ".to_owned());
         tokens.insert("task.description", "Type the task description: ".to_owned());
         tokens.insert("input.yes", "yes".to_owned());
         tokens.insert("input.no", "no".to_owned());
         tokens.insert("input.exit", "exit".to_owned());
+        tokens.insert("stats.title", "Statistics\n\n".to_owned());
+        tokens.insert("stats.total", "Total tasks:     ".to_owned());
+        tokens.insert("stats.open", "Open tasks:      ".to_owned());
+        tokens.insert("stats.done", "Completed tasks: ".to_owned());
+        tokens.insert("stats.rate", "Completion rate: ".to_owned());
+        tokens.insert("stats.weekly", "Created vs completed per week\n".to_owned());
+        tokens.insert("stats.created", "created   ".to_owned());
+        tokens.insert("stats.completed", "completed ".to_owned());
+        tokens.insert("stats.average", "Average time to complete: ".to_owned());
+        tokens.insert("stats.oldest", "Oldest open tasks\n".to_owned());
+        tokens.insert("stats.none", "Nothing to show\n".to_owned());
+        tokens.insert(
+            "audit.filter",
+            "Filter by user:, command:, result:, date: or any text (empty shows all): ".to_owned(),
+        );
+        tokens.insert("audit.none", "No entries found\n\n".to_owned());
+        tokens.insert(
+            "action.restore-backup",
+            "Replaces the tasks with a saved backup".to_owned(),
+        );
+        tokens.insert(
+            "action.restore-backup.backup",
+            "Type the backup number (1 is the newest): ".to_owned(),
+        );
         tokens.insert("menu.add", "Add      To add a new task\n".to_owned());
         tokens.insert("menu.remove", "Remove   To remove a task\n".to_owned());
         tokens.insert("menu.update", "Update   To update a task\n".to_owned());
         tokens.insert("menu.display", "Display  To display tasks\n".to_owned());
         tokens.insert("menu.complete", "Complete To complete a task\n".to_owned());
+        tokens.insert("menu.move", "Move     To reorder a task\n".to_owned());
+        tokens.insert("menu.merge", "Merge    To merge two tasks\n".to_owned());
         tokens.insert("menu.save", "Save     To save the tasks\n".to_owned());
+        tokens.insert("menu.stats", "Stats    To show the statistics\n".to_owned());
+        tokens.insert(
+            "menu.import",
+            "Import   To add the tasks of a file\n".to_owned(),
+        );
+        tokens.insert(
+            "menu.export",
+            "Export   To write the tasks to a file\n".to_owned(),
+        );
+        tokens.insert(
+            "menu.audit",
+            "Audit    To browse the audit log\n".to_owned(),
+        );
         tokens.insert("menu.exit", "Exit     To quit application\n\n".to_owned());
 
         tokens
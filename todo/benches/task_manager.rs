@@ -0,0 +1,82 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use todo::{task::Task, task_manager::TaskManager};
+
+const AMOUNT: u32 = 100_000;
+
+fn tasks() -> Vec<Task> {
+    (1..=AMOUNT)
+        .map(|id| Task {
+            id,
+            name: format!("Task {}", id),
+            description: format!("Description of the task {}", id),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn manager() -> TaskManager {
+    let mut manager = TaskManager::new();
+    manager.set_tasks(tasks());
+    manager
+}
+
+fn lookups(c: &mut Criterion) {
+    let mut manager = manager();
+
+    c.bench_function("get_by_id 100k", |b| {
+        b.iter(|| manager.get_by_id(black_box(AMOUNT / 2)).is_ok())
+    });
+
+    c.bench_function("complete_by 100k", |b| {
+        b.iter(|| manager.complete_by(black_box(AMOUNT - 1)))
+    });
+
+    c.bench_function("update_by 100k", |b| {
+        b.iter(|| manager.update_by(black_box(AMOUNT / 3), "Name", "Description"))
+    });
+}
+
+fn additions(c: &mut Criterion) {
+    c.bench_function("add 100k", |b| {
+        b.iter_batched_ref(
+            manager,
+            |manager| manager.add(black_box("Buy milk"), "Description"),
+            BatchSize::LargeInput,
+        )
+    });
+
+    c.bench_function("add with duplicate check 100k", |b| {
+        b.iter_batched_ref(
+            || manager().with_duplicate_check(),
+            |manager| manager.add(black_box("Buy milk"), "Description"),
+            BatchSize::LargeInput,
+        )
+    });
+
+    let manager = manager();
+
+    c.bench_function("find_similar 100k", |b| {
+        b.iter(|| manager.find_similar(black_box("Task 50000")).len())
+    });
+}
+
+fn removals(c: &mut Criterion) {
+    c.bench_function("remove_by 100k", |b| {
+        b.iter_batched_ref(
+            manager,
+            |manager| manager.remove_by(black_box(1)),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn store(c: &mut Criterion) {
+    let manager = manager();
+
+    c.bench_function("get_tasks_store 100k", |b| {
+        b.iter(|| manager.get_tasks_store().len())
+    });
+}
+
+criterion_group!(benches, lookups, additions, removals, store);
+criterion_main!(benches);
@@ -1,4 +1,4 @@
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ActionArgs {
     pub command: Option<String>,
     pub first: Option<String>,
@@ -15,17 +15,7 @@ impl ActionArgs {
     }
 }
 
-impl Default for ActionArgs {
-    fn default() -> Self {
-        Self {
-            command: Default::default(),
-            first: Default::default(),
-            second: Default::default(),
-            third: Default::default(),
-        }
-    }
-}
-
+#[derive(Default)]
 pub struct ActionArgsBuilder(ActionArgs);
 
 impl ActionArgsBuilder {
@@ -1,130 +1,403 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use crate::{
-    action_args::ActionArgs, display::DisplayMessage, repository::Repository,
+    action_args::ActionArgs,
+    command::Command,
+    custom_action::CustomAction,
+    diff::Diff,
+    error::{ActionError, LoadError},
+    middleware::Middleware,
+    outcome::Outcome,
+    repository::Repository,
+    statistics::Statistics,
+    task::{counter, reset_counter, Task},
     task_manager::TaskManager,
 };
 
-type ActionHandler =
-    fn(am: &mut ActionManger, args: ActionArgs, display: &dyn DisplayMessage) -> bool;
-
 pub struct ActionManger {
-    actions: HashMap<&'static str, ActionHandler>,
     manager: TaskManager,
     repository: Box<dyn Repository>,
+    actions: BTreeMap<&'static str, CustomAction>,
+    middlewares: Vec<Box<dyn Middleware>>,
+    load_error: Option<LoadError>,
+    /// Counts the changes to the tasks. See `revision`.
+    revision: u64,
 }
 
-impl ActionManger {
+pub struct ActionMangerBuilder {
+    repository: Box<dyn Repository>,
+    middlewares: Vec<Box<dyn Middleware>>,
+    check_duplicates: bool,
+}
+
+impl ActionMangerBuilder {
     pub fn new(repository: Box<dyn Repository>) -> Self {
-        let mut action_manager = Self {
-            actions: Self::actions_mapper(),
-            manager: TaskManager::new(),
+        Self {
             repository,
+            middlewares: Vec::new(),
+            check_duplicates: false,
+        }
+    }
+
+    /// Makes `add` report the open tasks with a similar name in its
+    /// outcome. See `TaskManager::with_duplicate_check`.
+    pub fn with_duplicate_check(mut self) -> Self {
+        self.check_duplicates = true;
+        self
+    }
+
+    /// Adds a middleware to the chain. They run in the order they were
+    /// added before a command, and in the opposite order after it.
+    pub fn with_middleware(mut self, middleware: Box<dyn Middleware>) -> Self {
+        self.middlewares.push(middleware);
+        self
+    }
+
+    pub fn build(self) -> ActionManger {
+        let manager = if self.check_duplicates {
+            TaskManager::new().with_duplicate_check()
+        } else {
+            TaskManager::new()
+        };
+
+        let mut action_manager = ActionManger {
+            manager,
+            repository: self.repository,
+            actions: BTreeMap::new(),
+            middlewares: self.middlewares,
+            load_error: None,
+            revision: 0,
         };
 
         action_manager.load();
 
         action_manager
     }
+}
+
+impl ActionManger {
+    pub fn new(repository: Box<dyn Repository>) -> Self {
+        ActionMangerBuilder::new(repository).build()
+    }
+
+    pub fn process(&mut self, mut command: Command) -> Result<Outcome, ActionError> {
+        let (passed, refused) = self.before(&mut command);
+
+        let result = match refused {
+            Ok(()) => self.run(command.clone()),
+            Err(error) => Err(error),
+        };
+
+        self.after(passed, &command, &result);
+
+        result
+    }
+
+    /// Runs the `before` middlewares until one refuses the command. Returns
+    /// how many let it through, for `after`, and the refusal if any.
+    pub(crate) fn before(&mut self, command: &mut Command) -> (usize, Result<(), ActionError>) {
+        for (passed, middleware) in self.middlewares.iter_mut().enumerate() {
+            if let Err(error) = middleware.before(command, &self.manager) {
+                return (passed, Err(error));
+            }
+        }
 
-    pub fn process(&mut self, args: ActionArgs, display: &dyn DisplayMessage) -> bool {
-        let command = args.command.clone().unwrap();
+        (self.middlewares.len(), Ok(()))
+    }
 
-        match self.select_action(command.as_str()) {
-            Ok(f) => f(self, args, display),
-            Err(_) => false,
+    pub(crate) fn after(
+        &mut self,
+        passed: usize,
+        command: &Command,
+        result: &Result<Outcome, ActionError>,
+    ) {
+        for middleware in self.middlewares[..passed].iter_mut().rev() {
+            middleware.after(command, result, &self.manager);
         }
     }
 
-    fn select_action(&mut self, action: &str) -> Result<ActionHandler, &'static str> {
-        match self.actions.get(&action) {
-            Some(f) => Ok(*f),
-            None => Err("Command not found."),
+    pub(crate) fn run(&mut self, command: Command) -> Result<Outcome, ActionError> {
+        let mutating = command.is_mutating();
+
+        let result = match command {
+            Command::Save => self.save(),
+            command => apply(&mut self.manager, &self.actions, command),
+        };
+
+        if mutating && result.is_ok() {
+            self.revision += 1;
         }
+
+        result
     }
 
-    fn actions_mapper() -> HashMap<&'static str, ActionHandler> {
-        let mut actions: HashMap<&'static str, ActionHandler> = HashMap::new();
+    /// Runs the command on a copy of the tasks and returns what it would
+    /// change. The tasks, the repository and the next task id are left as
+    /// they were, and the middlewares don't see it.
+    pub fn dry_run(&self, command: Command) -> Result<Diff, ActionError> {
+        let mut copy = self.manager.clone();
+        let next_id = counter();
+
+        let result = match command {
+            Command::Save if self.manager.in_transaction() => Err(ActionError::TransactionOpen),
+            command => apply(&mut copy, &self.actions, command),
+        };
 
-        actions.insert("add", ActionManger::add);
-        actions.insert("display", ActionManger::display);
-        actions.insert("remove", ActionManger::remove);
-        actions.insert("update", ActionManger::update);
-        actions.insert("complete", ActionManger::complete);
-        actions.insert("save", ActionManger::save);
+        // Only give back the ids the dry run took itself: if a task was
+        // made elsewhere meanwhile, the counter is past them and stays.
+        let taken = copy.get_tasks().filter(|task| task.id > next_id).count() as u32;
+        reset_counter(next_id + taken, next_id);
 
-        actions
+        result.map(|_| Diff::between(&self.manager, &copy))
     }
 
-    fn load(&mut self) {
-        let tasks = self.repository.load();
+    /// Adds an action that can then be run with `Command::Custom`. Its name
+    /// can't be one of the built-in commands or an action already added.
+    pub fn register(&mut self, action: CustomAction) -> Result<(), ActionError> {
+        let name = action.name();
 
-        self.manager.set_tasks(tasks);
+        if name.trim().is_empty() {
+            return Err(ActionError::empty("action"));
+        }
+
+        if Command::BUILT_IN.contains(&name) || self.actions.contains_key(name) {
+            return Err(ActionError::Validation {
+                field: "action",
+                reason: "already registered",
+            });
+        }
+
+        self.actions.insert(name, action);
+
+        Ok(())
+    }
+
+    pub fn custom_action(&self, name: &str) -> Option<&CustomAction> {
+        self.actions.get(name)
+    }
+
+    /// Returns the registered actions sorted by name.
+    pub fn custom_actions(&self) -> impl Iterator<Item = &CustomAction> {
+        self.actions.values()
     }
 
-    fn add(&mut self, args: ActionArgs, _display: &dyn DisplayMessage) -> bool {
-        let name = args.first.unwrap();
-        let description = args.second.unwrap();
+    /// Processes an action given in the old string form. Arguments that
+    /// can't be read as a `Command` make it fail.
+    pub fn process_args(&mut self, args: ActionArgs) -> Result<Outcome, ActionError> {
+        let command = Command::try_from(args)?;
 
-        self.manager.add(name.as_str(), description.as_str())
+        self.process(command)
     }
 
-    fn display(&mut self, _args: ActionArgs, display: &dyn DisplayMessage) -> bool {
-        self.manager.get_tasks().iter().for_each(|task| {
-            let message = format!("{}", task);
+    /// Processes every action inside one transaction: if any of them fails,
+    /// the list goes back to how it was before the first one and its error
+    /// is returned. Otherwise the outcome of each action is returned. The
+    /// transaction goes through the middlewares too, so they can tell the
+    /// actions apart from the ones that were undone.
+    pub fn process_all(&mut self, list: Vec<Command>) -> Result<Vec<Outcome>, ActionError> {
+        self.process(Command::Begin)?;
+
+        let mut outcomes = Vec::new();
+
+        for command in list {
+            match self.process(command) {
+                Ok(outcome) => outcomes.push(outcome),
+                Err(error) => {
+                    self.process(Command::Rollback)?;
+                    return Err(error);
+                }
+            }
+        }
 
-            display.show(message);
-        });
+        self.process(Command::Commit)?;
 
-        true
+        Ok(outcomes)
     }
 
-    fn remove(&mut self, args: ActionArgs, _display: &dyn DisplayMessage) -> bool {
-        let id = args.first.unwrap().parse::<u32>().unwrap();
-        self.manager.remove_by(id)
+    pub fn statistics(&self) -> Statistics {
+        self.manager.statistics()
     }
 
-    fn update(&mut self, args: ActionArgs, _display: &dyn DisplayMessage) -> bool {
-        let id = args.third.unwrap().parse::<u32>().unwrap();
+    /// Returns a copy of the tasks in their order.
+    pub fn tasks(&self) -> Vec<Task> {
+        self.manager.get_tasks().cloned().collect()
+    }
 
-        let name = args.first.unwrap();
-        let description = args.second.unwrap();
+    pub fn in_transaction(&self) -> bool {
+        self.manager.in_transaction()
+    }
 
-        self.manager
-            .update_by(id, name.as_str(), description.as_str())
+    /// Changes whenever the tasks may have changed: after every command
+    /// that can change them and succeeded, and after `recover`. A copy of
+    /// `tasks` only needs taking again once this differs.
+    pub fn revision(&self) -> u64 {
+        self.revision
     }
 
-    fn complete(&mut self, args: ActionArgs, _display: &dyn DisplayMessage) -> bool {
-        let id = args.first.unwrap().parse::<u32>().unwrap();
-        self.manager.complete_by(id)
+    pub fn similar_tasks(&self, name: &str) -> Vec<&Task> {
+        self.manager.find_similar(name)
     }
 
-    fn save(&mut self, _args: ActionArgs, _display: &dyn DisplayMessage) -> bool {
-        self.repository.save(self.manager.get_tasks_store())
+    pub fn preview_merge(&self, keep: u32, other: u32) -> Result<Task, ActionError> {
+        self.manager.merged(keep, other)
+    }
+
+    /// Returns why the tasks couldn't be loaded, if they couldn't. The
+    /// list then starts empty, and saving it would replace what is stored,
+    /// so check this before anything else and `recover` first.
+    pub fn take_load_error(&mut self) -> Option<LoadError> {
+        self.load_error.take()
+    }
+
+    /// Replaces the tasks with `tasks`, for example the ones salvaged from
+    /// a `LoadError`. Nothing is stored until the next save.
+    pub fn recover(&mut self, tasks: Vec<Task>) {
+        self.manager.set_tasks(tasks);
+        self.revision += 1;
+    }
+
+    fn load(&mut self) {
+        match self.repository.load() {
+            Ok(tasks) => self.manager.set_tasks(tasks),
+            Err(error) => self.load_error = Some(error),
+        }
+    }
+
+    fn save(&mut self) -> Result<Outcome, ActionError> {
+        if self.manager.in_transaction() {
+            return Err(ActionError::TransactionOpen);
+        }
+
+        let tasks = self.manager.get_tasks_store();
+        let saved = tasks.len();
+
+        if self.repository.save(tasks) {
+            Ok(Outcome::Saved(saved))
+        } else {
+            Err(ActionError::Storage(
+                "the tasks couldn't be written".to_string(),
+            ))
+        }
     }
 }
 
+/// Runs a command on the tasks. Saving is left to the caller.
+fn apply(
+    manager: &mut TaskManager,
+    actions: &BTreeMap<&'static str, CustomAction>,
+    command: Command,
+) -> Result<Outcome, ActionError> {
+    match command {
+        Command::Add { name, description } => {
+            let added = manager.add(&name, &description)?;
+
+            Ok(Outcome::Added {
+                task: manager.get_by_id(added.id)?.clone(),
+                similar: added.similar,
+            })
+        }
+        Command::Display => Ok(Outcome::Tasks(manager.get_tasks().cloned().collect())),
+        Command::Remove { id } => {
+            let removed = manager.get_by_id(id)?.clone();
+
+            manager.remove_by(id)?;
+
+            Ok(Outcome::Task(removed))
+        }
+        Command::Update {
+            id,
+            name,
+            description,
+        } => {
+            manager.update_by(id, &name, &description)?;
+
+            task(manager, id)
+        }
+        Command::Complete { id } => {
+            manager.complete_by(id)?;
+
+            task(manager, id)
+        }
+        Command::Move { id, placement } => {
+            manager.move_by(id, placement)?;
+
+            task(manager, id)
+        }
+        Command::Merge { keep, other } => {
+            manager.merge(keep, other)?;
+
+            task(manager, keep)
+        }
+        Command::Import { tasks } => {
+            let ids = manager.import(tasks)?;
+
+            Ok(Outcome::Tasks(
+                ids.iter()
+                    .map(|id| manager.get_by_id(*id).cloned())
+                    .collect::<Result<_, _>>()?,
+            ))
+        }
+        Command::Stats => Ok(Outcome::Statistics(manager.statistics())),
+        Command::Begin => manager.begin().map(|()| Outcome::Done),
+        Command::Commit => manager.commit().map(|()| Outcome::Done),
+        Command::Rollback => manager.rollback().map(|()| Outcome::Done),
+        Command::Custom { name, args } => match actions.get(name.as_str()) {
+            Some(action) => action.run(manager, &args).map(|()| Outcome::Done),
+            None => Err(ActionError::UnknownCommand(name)),
+        },
+        Command::Save => Ok(Outcome::Done),
+    }
+}
+
+fn task(manager: &TaskManager, id: u32) -> Result<Outcome, ActionError> {
+    Ok(Outcome::Task(manager.get_by_id(id)?.clone()))
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{action_args::ActionArgsBuilder, display::DisplayMessage, repository::Repository};
-
-    use super::ActionManger;
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        action_args::ActionArgsBuilder,
+        command::Command,
+        custom_action::CustomAction,
+        diff::Change,
+        error::{ActionError, LoadError},
+        middleware::Middleware,
+        outcome::Outcome,
+        repository::Repository,
+        task::Task,
+        task_manager::TaskManager,
+    };
+
+    use super::{ActionManger, ActionMangerBuilder};
 
     #[derive(Clone)]
     struct Test;
 
     impl Repository for Test {
-        fn save(&mut self, _tasks: Vec<crate::task::Task>) -> bool {
+        fn save(&mut self, _tasks: Vec<&Task>) -> bool {
             true
         }
 
-        fn load(&mut self) -> Vec<crate::task::Task> {
-            Vec::new()
+        fn load(&mut self) -> Result<Vec<Task>, LoadError> {
+            Ok(Vec::new())
         }
     }
 
-    impl DisplayMessage for Test {
-        fn show(&self, _message: String) {}
+    struct Corrupt;
+
+    impl Repository for Corrupt {
+        fn save(&mut self, _tasks: Vec<&Task>) -> bool {
+            true
+        }
+
+        fn load(&mut self) -> Result<Vec<Task>, LoadError> {
+            Err(LoadError::Corrupt {
+                reason: "truncated".to_string(),
+                salvaged: vec![Task::new("Salvaged", "Description").unwrap()],
+            })
+        }
     }
 
     #[test]
@@ -137,9 +410,9 @@ mod tests {
             .with_second("Description".to_string())
             .build();
 
-        let mut action_manager = ActionManger::new(Box::new (test.clone()));
+        let mut action_manager = ActionManger::new(Box::new(test.clone()));
 
-        assert_eq!(action_manager.process(args, &test), true);
+        assert!(action_manager.process_args(args).is_ok());
     }
 
     #[test]
@@ -152,9 +425,12 @@ mod tests {
             .with_second("Description".to_string())
             .build();
 
-        let mut action_manager = ActionManger::new(Box::new (test.clone()));
+        let mut action_manager = ActionManger::new(Box::new(test.clone()));
 
-        assert_eq!(action_manager.process(args, &test), false);
+        assert_eq!(
+            action_manager.process_args(args),
+            Err(ActionError::empty("name"))
+        );
     }
 
     #[test]
@@ -167,8 +443,329 @@ mod tests {
             .with_second("".to_string())
             .build();
 
-        let mut action_manager = ActionManger::new(Box::new (test.clone()));
+        let mut action_manager = ActionManger::new(Box::new(test.clone()));
+
+        assert_eq!(
+            action_manager.process_args(args),
+            Err(ActionError::empty("description"))
+        );
+    }
+
+    #[test]
+    fn test_remove_reports_why_it_failed() {
+        let test = Test;
+
+        let remove = |id: &str| {
+            ActionArgsBuilder::new()
+                .with_command("remove")
+                .with_first(id.to_string())
+                .build()
+        };
+
+        let mut action_manager = ActionManger::new(Box::new(test.clone()));
+
+        assert_eq!(
+            action_manager.process_args(remove("one")),
+            Err(ActionError::InvalidId("one".to_string()))
+        );
+        assert_eq!(
+            action_manager.process_args(remove("42")),
+            Err(ActionError::NotFound(42))
+        );
+    }
+
+    #[test]
+    fn test_register_custom_action() {
+        let test = Test;
+
+        let mut action_manager = ActionManger::new(Box::new(test.clone()));
+
+        let clear = || {
+            CustomAction::new("clear", "Removes the done tasks", |manager, _| {
+                let done: Vec<u32> = manager
+                    .get_tasks()
+                    .filter(|task| task.done)
+                    .map(|task| task.id)
+                    .collect();
+
+                done.into_iter().try_for_each(|id| manager.remove_by(id))
+            })
+        };
+
+        assert!(action_manager.register(clear()).is_ok());
+        assert!(action_manager.register(clear()).is_err());
+        assert!(action_manager
+            .register(CustomAction::new("add", "", |_, _| Ok(())))
+            .is_err());
+
+        let add = |name: &str| Command::Add {
+            name: name.to_string(),
+            description: "Description".to_string(),
+        };
+
+        assert!(action_manager
+            .process_all(vec![add("First"), add("Second")])
+            .is_ok());
+
+        let id = action_manager.manager.get_tasks().next().unwrap().id;
+        assert!(action_manager.process(Command::Complete { id }).is_ok());
+
+        let args = ActionArgsBuilder::new().with_command("clear").build();
+        assert!(action_manager.process_args(args).is_ok());
+        assert_eq!(action_manager.statistics().total, 1);
+
+        let args = ActionArgsBuilder::new().with_command("fly").build();
+        assert_eq!(
+            action_manager.process_args(args),
+            Err(ActionError::UnknownCommand("fly".to_string()))
+        );
+    }
+
+    struct ReadOnly;
+
+    impl Middleware for ReadOnly {
+        fn before(
+            &mut self,
+            command: &mut Command,
+            _tasks: &TaskManager,
+        ) -> Result<(), ActionError> {
+            match command {
+                Command::Remove { .. } => Err(ActionError::Rejected("read only".to_string())),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    struct Upper(Arc<Mutex<Vec<String>>>);
+
+    impl Middleware for Upper {
+        fn before(
+            &mut self,
+            command: &mut Command,
+            _tasks: &TaskManager,
+        ) -> Result<(), ActionError> {
+            if let Command::Add { name, .. } = command {
+                *name = name.to_uppercase();
+            }
+
+            Ok(())
+        }
+
+        fn after(
+            &mut self,
+            command: &Command,
+            result: &Result<Outcome, ActionError>,
+            _tasks: &TaskManager,
+        ) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", command.name(), result.is_ok()));
+        }
+    }
+
+    #[test]
+    fn test_middlewares() {
+        let test = Test;
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let mut action_manager = ActionMangerBuilder::new(Box::new(test.clone()))
+            .with_middleware(Box::new(Upper(log.clone())))
+            .with_middleware(Box::new(ReadOnly))
+            .build();
+
+        let add = Command::Add {
+            name: "Name".to_string(),
+            description: "Description".to_string(),
+        };
+
+        assert!(action_manager.process(add).is_ok());
+
+        let task = action_manager.manager.get_tasks().next().unwrap().clone();
+        assert_eq!(task.name, "NAME");
+
+        assert_eq!(
+            action_manager.process(Command::Remove { id: task.id }),
+            Err(ActionError::Rejected("read only".to_string()))
+        );
+        assert_eq!(action_manager.statistics().total, 1);
+
+        assert_eq!(*log.lock().unwrap(), vec!["add true", "remove false"]);
+    }
+
+    #[test]
+    fn test_dry_run_changes_nothing() {
+        let test = Test;
+
+        let mut action_manager = ActionManger::new(Box::new(test.clone()));
+
+        let add = |name: &str| Command::Add {
+            name: name.to_string(),
+            description: "Description".to_string(),
+        };
+
+        assert!(action_manager.process(add("First")).is_ok());
+
+        let diff = action_manager.dry_run(add("Second")).unwrap();
+        assert!(matches!(&diff.changes[..], [Change::Added(task)] if task.name == "Second"));
+        assert_eq!(action_manager.statistics().total, 1);
+
+        assert_eq!(
+            action_manager.dry_run(Command::Remove { id: 999 }),
+            Err(ActionError::NotFound(999))
+        );
+    }
+
+    #[test]
+    fn test_dry_run_keeps_ids_taken_meanwhile() {
+        let elsewhere = Arc::new(Mutex::new(None));
+        let seen = elsewhere.clone();
+
+        let mut action_manager = ActionManger::new(Box::new(Test));
+
+        action_manager
+            .register(CustomAction::new(
+                "add-here",
+                "Adds a task while another is made elsewhere",
+                move |manager, _| {
+                    *seen.lock().unwrap() = Some(Task::new("Elsewhere", "Description")?.id);
+                    manager.add("Here", "Description").map(|_| ())
+                },
+            ))
+            .unwrap();
+
+        action_manager
+            .dry_run(Command::Custom {
+                name: "add-here".to_string(),
+                args: Vec::new(),
+            })
+            .unwrap();
+
+        let elsewhere = elsewhere.lock().unwrap().unwrap();
+
+        match action_manager.process(Command::Add {
+            name: "Next".to_string(),
+            description: "Description".to_string(),
+        }) {
+            Ok(Outcome::Added { task, .. }) => assert_ne!(task.id, elsewhere),
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn test_outcomes() {
+        let test = Test;
+
+        let mut action_manager = ActionManger::new(Box::new(test.clone()));
+
+        let added = match action_manager.process(Command::Add {
+            name: "Name".to_string(),
+            description: "Description".to_string(),
+        }) {
+            Ok(Outcome::Added { task, similar }) if similar.is_empty() => task,
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        };
+        assert_eq!(added.name, "Name");
+
+        assert!(matches!(
+            action_manager.process(Command::Complete { id: added.id }),
+            Ok(Outcome::Task(task)) if task.done
+        ));
+        assert!(matches!(
+            action_manager.process(Command::Display),
+            Ok(Outcome::Tasks(tasks)) if tasks.len() == 1
+        ));
+        assert!(matches!(
+            action_manager.process(Command::Stats),
+            Ok(Outcome::Statistics(statistics)) if statistics.done == 1
+        ));
+        assert_eq!(action_manager.process(Command::Save), Ok(Outcome::Saved(1)));
+        assert!(matches!(
+            action_manager.process(Command::Remove { id: added.id }),
+            Ok(Outcome::Task(task)) if task.id == added.id
+        ));
+    }
+
+    #[test]
+    fn test_add_reports_duplicates_when_asked() {
+        let add = |action_manager: &mut ActionManger, name: &str| match action_manager.process(
+            Command::Add {
+                name: name.to_string(),
+                description: "Description".to_string(),
+            },
+        ) {
+            Ok(Outcome::Added { task, similar }) => (task.id, similar),
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        };
+
+        let mut checked = ActionMangerBuilder::new(Box::new(Test))
+            .with_duplicate_check()
+            .build();
+
+        let (first, similar) = add(&mut checked, "Buy milk");
+        assert!(similar.is_empty());
+        assert_eq!(add(&mut checked, "buy milk!").1, vec![first]);
+
+        let mut unchecked = ActionManger::new(Box::new(Test));
+
+        add(&mut unchecked, "Buy milk");
+        assert!(add(&mut unchecked, "buy milk!").1.is_empty());
+    }
+
+    #[test]
+    fn test_save_refused_during_transaction() {
+        let test = Test;
+
+        let mut action_manager = ActionManger::new(Box::new(test.clone()));
+
+        assert!(action_manager.process(Command::Begin).is_ok());
+        assert_eq!(
+            action_manager.process(Command::Save),
+            Err(ActionError::TransactionOpen)
+        );
+        assert!(action_manager.process(Command::Commit).is_ok());
+        assert!(action_manager.process(Command::Save).is_ok());
+    }
+
+    #[test]
+    fn test_process_all_rolls_back_on_failure() {
+        let test = Test;
+
+        let add = |name: &str| Command::Add {
+            name: name.to_string(),
+            description: "Description".to_string(),
+        };
+
+        let mut action_manager = ActionManger::new(Box::new(test.clone()));
+
+        assert_eq!(
+            action_manager.process_all(vec![add("First"), add(""), add("Third")]),
+            Err(ActionError::empty("name"))
+        );
+        assert_eq!(action_manager.statistics().total, 0);
+
+        assert!(action_manager
+            .process_all(vec![add("First"), add("Second")])
+            .is_ok());
+        assert_eq!(action_manager.statistics().total, 2);
+        assert!(action_manager.process(Command::Save).is_ok());
+    }
+
+    #[test]
+    fn test_load_error_and_recover() {
+        let mut action_manager = ActionManger::new(Box::new(Corrupt));
+
+        assert!(action_manager.tasks().is_empty());
+
+        let salvaged = match action_manager.take_load_error() {
+            Some(LoadError::Corrupt { salvaged, .. }) => salvaged,
+            other => panic!("expected a corrupt load, got {:?}", other),
+        };
+
+        assert_eq!(action_manager.take_load_error(), None);
+
+        action_manager.recover(salvaged);
 
-        assert_eq!(action_manager.process(args, &test), false);
+        assert_eq!(action_manager.tasks()[0].name, "Salvaged");
     }
 }
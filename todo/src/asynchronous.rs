@@ -0,0 +1,263 @@
+//! Async counterparts of `Repository`, `Reader` and `ActionManger`, for
+//! embedding the crate in async services. Enabled by the `async` feature.
+
+use async_trait::async_trait;
+use futures::executor::block_on;
+
+use crate::{
+    action_manager::{ActionManger, ActionMangerBuilder},
+    command::Command,
+    error::{ActionError, LoadError},
+    middleware::Middleware,
+    outcome::Outcome,
+    reader::Reader,
+    repository::Repository,
+    task::Task,
+};
+
+#[async_trait]
+pub trait AsyncRepository: Send {
+    async fn save(&mut self, tasks: Vec<&Task>) -> bool;
+    async fn load(&mut self) -> Result<Vec<Task>, LoadError>;
+}
+
+#[async_trait]
+pub trait AsyncReader: Send {
+    /// Returns the next line, or `None` once the input is over.
+    async fn read(&mut self) -> Option<String>;
+}
+
+/// Uses a blocking implementation where an async one is expected. Each
+/// call still blocks the thread it runs on.
+pub struct Blocking<T>(pub T);
+
+#[async_trait]
+impl<R: Repository> AsyncRepository for Blocking<R> {
+    async fn save(&mut self, tasks: Vec<&Task>) -> bool {
+        self.0.save(tasks)
+    }
+
+    async fn load(&mut self) -> Result<Vec<Task>, LoadError> {
+        self.0.load()
+    }
+}
+
+#[async_trait]
+impl<R: Reader + Send> AsyncReader for Blocking<R> {
+    async fn read(&mut self) -> Option<String> {
+        self.0.read()
+    }
+}
+
+/// Uses an async implementation where a blocking one is expected, by
+/// waiting for each call to finish. Don't use it inside an async runtime.
+pub struct Waiting<T>(pub T);
+
+impl<R: AsyncRepository + 'static> Repository for Waiting<R> {
+    fn save(&mut self, tasks: Vec<&Task>) -> bool {
+        block_on(self.0.save(tasks))
+    }
+
+    fn load(&mut self) -> Result<Vec<Task>, LoadError> {
+        block_on(self.0.load())
+    }
+}
+
+impl<R: AsyncReader + 'static> Reader for Waiting<R> {
+    fn read(&mut self) -> Option<String> {
+        block_on(self.0.read())
+    }
+}
+
+/// Stands in for the repository of the wrapped `ActionManger`: it hands
+/// over what `AsyncActionManger` loaded, tasks or error, and can't save,
+/// since saving goes through the async repository.
+struct Loaded(Option<Result<Vec<Task>, LoadError>>);
+
+impl Repository for Loaded {
+    fn save(&mut self, _tasks: Vec<&Task>) -> bool {
+        false
+    }
+
+    fn load(&mut self) -> Result<Vec<Task>, LoadError> {
+        self.0.take().unwrap_or(Ok(Vec::new()))
+    }
+}
+
+/// An `ActionManger` whose storage is an `AsyncRepository`. Every action
+/// behaves as in `ActionManger`, middlewares included; only loading and
+/// saving wait on the repository.
+pub struct AsyncActionManger {
+    action_manager: ActionManger,
+    repository: Box<dyn AsyncRepository>,
+}
+
+impl AsyncActionManger {
+    /// Loads the tasks. If that fails, the error is kept as it would be
+    /// in `ActionManger`: see `ActionManger::take_load_error`.
+    pub async fn new(repository: Box<dyn AsyncRepository>) -> Self {
+        Self::with_middlewares(repository, Vec::new()).await
+    }
+
+    pub async fn with_middlewares(
+        mut repository: Box<dyn AsyncRepository>,
+        middlewares: Vec<Box<dyn Middleware>>,
+    ) -> Self {
+        let tasks = repository.load().await;
+
+        let action_manager = middlewares
+            .into_iter()
+            .fold(
+                ActionMangerBuilder::new(Box::new(Loaded(Some(tasks)))),
+                |builder, middleware| builder.with_middleware(middleware),
+            )
+            .build();
+
+        Self {
+            action_manager,
+            repository,
+        }
+    }
+
+    pub async fn process(&mut self, mut command: Command) -> Result<Outcome, ActionError> {
+        let (passed, refused) = self.action_manager.before(&mut command);
+
+        let result = match (refused, &command) {
+            (Err(error), _) => Err(error),
+            (Ok(()), Command::Save) => self.save().await,
+            (Ok(()), _) => self.action_manager.run(command.clone()),
+        };
+
+        self.action_manager.after(passed, &command, &result);
+
+        result
+    }
+
+    /// Same as `ActionManger::process_all`.
+    pub async fn process_all(&mut self, list: Vec<Command>) -> Result<Vec<Outcome>, ActionError> {
+        self.process(Command::Begin).await?;
+
+        let mut outcomes = Vec::new();
+
+        for command in list {
+            match self.process(command).await {
+                Ok(outcome) => outcomes.push(outcome),
+                Err(error) => {
+                    self.process(Command::Rollback).await?;
+                    return Err(error);
+                }
+            }
+        }
+
+        self.process(Command::Commit).await?;
+
+        Ok(outcomes)
+    }
+
+    /// Gives access to the rest of the `ActionManger` API. Saving must go
+    /// through `AsyncActionManger::process`: the wrapped manager can't.
+    pub fn action_manager(&mut self) -> &mut ActionManger {
+        &mut self.action_manager
+    }
+
+    async fn save(&mut self) -> Result<Outcome, ActionError> {
+        if self.action_manager.in_transaction() {
+            return Err(ActionError::TransactionOpen);
+        }
+
+        let tasks = self.action_manager.tasks();
+
+        if self.repository.save(tasks.iter().collect()).await {
+            Ok(Outcome::Saved(tasks.len()))
+        } else {
+            Err(ActionError::Storage(
+                "the tasks couldn't be written".to_string(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use futures::executor::block_on;
+
+    use crate::{
+        command::Command,
+        error::{ActionError, LoadError},
+        repository::Repository,
+        task::Task,
+    };
+
+    use super::{AsyncActionManger, AsyncRepository, Blocking, Waiting};
+
+    #[derive(Clone, Default)]
+    struct Memory(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl AsyncRepository for Memory {
+        async fn save(&mut self, tasks: Vec<&Task>) -> bool {
+            *self.0.lock().unwrap() = tasks.iter().map(|task| task.name.clone()).collect();
+            true
+        }
+
+        async fn load(&mut self) -> Result<Vec<Task>, LoadError> {
+            Ok(vec![Task::new("Loaded", "Description").unwrap()])
+        }
+    }
+
+    #[test]
+    fn test_async_action_manager() {
+        let memory = Memory::default();
+
+        block_on(async {
+            let mut action_manager = AsyncActionManger::new(Box::new(memory.clone())).await;
+
+            let add = Command::Add {
+                name: "Added".to_string(),
+                description: "Description".to_string(),
+            };
+
+            assert!(action_manager.process(add).await.is_ok());
+            assert!(action_manager.process(Command::Begin).await.is_ok());
+            assert_eq!(
+                action_manager.process(Command::Save).await,
+                Err(ActionError::TransactionOpen)
+            );
+            assert!(action_manager.process(Command::Commit).await.is_ok());
+            assert!(action_manager.process(Command::Save).await.is_ok());
+        });
+
+        assert_eq!(*memory.0.lock().unwrap(), vec!["Loaded", "Added"]);
+    }
+
+    struct Local;
+
+    impl Repository for Local {
+        fn save(&mut self, tasks: Vec<&Task>) -> bool {
+            !tasks.is_empty()
+        }
+
+        fn load(&mut self) -> Result<Vec<Task>, LoadError> {
+            Ok(vec![Task::new("Loaded", "Description").unwrap()])
+        }
+    }
+
+    #[test]
+    fn test_adapters() {
+        let memory = Memory::default();
+        let mut waiting = Waiting(memory.clone());
+
+        let tasks = waiting.load().unwrap();
+        assert!(waiting.save(tasks.iter().collect()));
+        assert_eq!(*memory.0.lock().unwrap(), vec!["Loaded"]);
+
+        let mut blocking = Blocking(Local);
+
+        let tasks = block_on(blocking.load()).unwrap();
+        assert!(block_on(blocking.save(tasks.iter().collect())));
+        assert!(!block_on(blocking.save(Vec::new())));
+    }
+}
@@ -0,0 +1,330 @@
+use crate::{action_args::ActionArgs, error::ActionError, task::Task, task_manager::Placement};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Add {
+        name: String,
+        description: String,
+    },
+    Display,
+    Remove {
+        id: u32,
+    },
+    Update {
+        id: u32,
+        name: String,
+        description: String,
+    },
+    Complete {
+        id: u32,
+    },
+    Move {
+        id: u32,
+        placement: Placement,
+    },
+    Merge {
+        keep: u32,
+        other: u32,
+    },
+    /// Adds tasks read from elsewhere, with fresh ids. See
+    /// `TaskManager::import`.
+    Import {
+        tasks: Vec<Task>,
+    },
+    Stats,
+    Save,
+    Begin,
+    Commit,
+    Rollback,
+    Custom {
+        name: String,
+        args: Vec<String>,
+    },
+}
+
+impl Command {
+    /// Names of the commands this crate handles itself, which custom
+    /// actions can't take.
+    pub const BUILT_IN: [&'static str; 13] = [
+        "add", "display", "remove", "update", "complete", "move", "merge", "import", "stats",
+        "save", "begin", "commit", "rollback",
+    ];
+
+    pub fn name(&self) -> &str {
+        match self {
+            Command::Add { .. } => "add",
+            Command::Display => "display",
+            Command::Remove { .. } => "remove",
+            Command::Update { .. } => "update",
+            Command::Complete { .. } => "complete",
+            Command::Move { .. } => "move",
+            Command::Merge { .. } => "merge",
+            Command::Import { .. } => "import",
+            Command::Stats => "stats",
+            Command::Save => "save",
+            Command::Begin => "begin",
+            Command::Commit => "commit",
+            Command::Rollback => "rollback",
+            Command::Custom { name, .. } => name,
+        }
+    }
+
+    /// Whether the command can change the tasks. Custom actions are
+    /// assumed to.
+    pub fn is_mutating(&self) -> bool {
+        !matches!(self, Command::Display | Command::Stats | Command::Save)
+    }
+
+    /// Returns the fields in the order `TryFrom<ActionArgs>` reads them, or
+    /// the task names for `import`.
+    pub fn args(&self) -> Vec<String> {
+        match self {
+            Command::Add { name, description } => vec![name.clone(), description.clone()],
+            Command::Remove { id } | Command::Complete { id } => vec![id.to_string()],
+            Command::Update {
+                id,
+                name,
+                description,
+            } => vec![name.clone(), description.clone(), id.to_string()],
+            Command::Move { id, placement } => {
+                let mut args = vec![id.to_string()];
+
+                match placement {
+                    Placement::Up => args.push("up".to_string()),
+                    Placement::Down => args.push("down".to_string()),
+                    Placement::Top => args.push("top".to_string()),
+                    Placement::Bottom => args.push("bottom".to_string()),
+                    Placement::Before(target) => {
+                        args.push("before".to_string());
+                        args.push(target.to_string());
+                    }
+                }
+
+                args
+            }
+            Command::Merge { keep, other } => vec![keep.to_string(), other.to_string()],
+            Command::Import { tasks } => tasks.iter().map(|task| task.name.clone()).collect(),
+            Command::Custom { args, .. } => args.clone(),
+            Command::Display
+            | Command::Stats
+            | Command::Save
+            | Command::Begin
+            | Command::Commit
+            | Command::Rollback => Vec::new(),
+        }
+    }
+}
+
+/// Reads the old string form, where the command name comes in `command`
+/// and its fields in `first`, `second` and `third`:
+///
+/// - `add`: name, description
+/// - `update`: name, description, id
+/// - `remove`, `complete`: id
+/// - `move`: id, `up`/`down`/`top`/`bottom`/`before`, id to move before
+/// - `merge`: id to keep, id to merge into it
+///
+/// `import` can't be read this way, since its tasks don't fit in fields.
+/// Any other name is read as a custom action, with the fields given as its
+/// arguments.
+impl TryFrom<ActionArgs> for Command {
+    type Error = ActionError;
+
+    fn try_from(args: ActionArgs) -> Result<Self, Self::Error> {
+        let command = args.command.ok_or(ActionError::missing("command"))?;
+
+        let command = match command.as_str() {
+            "add" => Command::Add {
+                name: field(args.first, "name")?,
+                description: field(args.second, "description")?,
+            },
+            "display" => Command::Display,
+            "remove" => Command::Remove {
+                id: id(args.first, "id")?,
+            },
+            "update" => Command::Update {
+                id: id(args.third, "id")?,
+                name: field(args.first, "name")?,
+                description: field(args.second, "description")?,
+            },
+            "complete" => Command::Complete {
+                id: id(args.first, "id")?,
+            },
+            "move" => Command::Move {
+                id: id(args.first, "id")?,
+                placement: match field(args.second, "placement")?.as_str() {
+                    "up" => Placement::Up,
+                    "down" => Placement::Down,
+                    "top" => Placement::Top,
+                    "bottom" => Placement::Bottom,
+                    "before" => Placement::Before(id(args.third, "target")?),
+                    _ => {
+                        return Err(ActionError::Validation {
+                            field: "placement",
+                            reason: "invalid",
+                        })
+                    }
+                },
+            },
+            "merge" => Command::Merge {
+                keep: id(args.first, "keep")?,
+                other: id(args.second, "other")?,
+            },
+            "import" => {
+                return Err(ActionError::Validation {
+                    field: "import",
+                    reason: "takes tasks, not fields",
+                })
+            }
+            "stats" => Command::Stats,
+            "save" => Command::Save,
+            "begin" => Command::Begin,
+            "commit" => Command::Commit,
+            "rollback" => Command::Rollback,
+            _ => Command::Custom {
+                args: [args.first, args.second, args.third]
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+                name: command,
+            },
+        };
+
+        Ok(command)
+    }
+}
+
+fn field(value: Option<String>, name: &'static str) -> Result<String, ActionError> {
+    value.ok_or(ActionError::missing(name))
+}
+
+fn id(value: Option<String>, name: &'static str) -> Result<u32, ActionError> {
+    let value = field(value, name)?;
+
+    value
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| ActionError::InvalidId(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{action_args::ActionArgsBuilder, error::ActionError, task_manager::Placement};
+
+    use super::Command;
+
+    #[test]
+    fn test_parse_update_reads_id_from_third() {
+        let args = ActionArgsBuilder::new()
+            .with_command("update")
+            .with_first("Name".to_string())
+            .with_second("Description".to_string())
+            .with_third("7".to_string())
+            .build();
+
+        assert_eq!(
+            Command::try_from(args),
+            Ok(Command::Update {
+                id: 7,
+                name: "Name".to_string(),
+                description: "Description".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_move_before() {
+        let args = ActionArgsBuilder::new()
+            .with_command("move")
+            .with_first("3".to_string())
+            .with_second("before".to_string())
+            .with_third("1".to_string())
+            .build();
+
+        assert_eq!(
+            Command::try_from(args),
+            Ok(Command::Move {
+                id: 3,
+                placement: Placement::Before(1),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_custom() {
+        let args = ActionArgsBuilder::new()
+            .with_command("rename")
+            .with_first("3".to_string())
+            .with_second("Name".to_string())
+            .build();
+
+        assert_eq!(
+            Command::try_from(args),
+            Ok(Command::Custom {
+                name: "rename".to_string(),
+                args: vec!["3".to_string(), "Name".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn test_args_parse_back() {
+        let commands = [
+            Command::Update {
+                id: 7,
+                name: "Name".to_string(),
+                description: "Description".to_string(),
+            },
+            Command::Move {
+                id: 3,
+                placement: Placement::Before(1),
+            },
+            Command::Merge { keep: 1, other: 2 },
+            Command::Save,
+        ];
+
+        for command in commands {
+            let mut args = command.args().into_iter();
+
+            let mut builder = ActionArgsBuilder::new().with_command(command.name());
+
+            if let Some(first) = args.next() {
+                builder = builder.with_first(first);
+            }
+            if let Some(second) = args.next() {
+                builder = builder.with_second(second);
+            }
+            if let Some(third) = args.next() {
+                builder = builder.with_third(third);
+            }
+
+            assert_eq!(Command::try_from(builder.build()), Ok(command));
+        }
+
+        assert!(!Command::Save.is_mutating());
+        assert!(Command::Remove { id: 1 }.is_mutating());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let remove = |id: &str| {
+            ActionArgsBuilder::new()
+                .with_command("remove")
+                .with_first(id.to_string())
+                .build()
+        };
+
+        assert_eq!(
+            Command::try_from(remove("x")),
+            Err(ActionError::InvalidId("x".to_string()))
+        );
+        assert_eq!(
+            Command::try_from(ActionArgsBuilder::new().with_command("remove").build()),
+            Err(ActionError::missing("id"))
+        );
+        assert_eq!(
+            Command::try_from(ActionArgsBuilder::new().build()),
+            Err(ActionError::missing("command"))
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{error::ActionError, task_manager::TaskManager};
+
+pub type ActionHandler = Box<dyn Fn(&mut TaskManager, &[String]) -> Result<(), ActionError> + Send>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArgumentKind {
+    Text,
+    Id,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Argument {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub kind: ArgumentKind,
+}
+
+/// An action added by a program built on this crate. It is registered in
+/// the `ActionManger` and run through `Command::Custom`, after its
+/// arguments are checked against the ones it declared.
+pub struct CustomAction {
+    name: &'static str,
+    help: &'static str,
+    arguments: Vec<Argument>,
+    handler: ActionHandler,
+}
+
+impl CustomAction {
+    pub fn new<F>(name: &'static str, help: &'static str, handler: F) -> Self
+    where
+        F: Fn(&mut TaskManager, &[String]) -> Result<(), ActionError> + Send + 'static,
+    {
+        Self {
+            name,
+            help,
+            arguments: Vec::new(),
+            handler: Box::new(handler),
+        }
+    }
+
+    pub fn with_text(mut self, name: &'static str, help: &'static str) -> Self {
+        self.arguments.push(Argument {
+            name,
+            help,
+            kind: ArgumentKind::Text,
+        });
+        self
+    }
+
+    pub fn with_id(mut self, name: &'static str, help: &'static str) -> Self {
+        self.arguments.push(Argument {
+            name,
+            help,
+            kind: ArgumentKind::Id,
+        });
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn help(&self) -> &'static str {
+        self.help
+    }
+
+    pub fn arguments(&self) -> &[Argument] {
+        &self.arguments
+    }
+
+    pub fn run(&self, manager: &mut TaskManager, args: &[String]) -> Result<(), ActionError> {
+        self.check(args)?;
+
+        (self.handler)(manager, args)
+    }
+
+    fn check(&self, args: &[String]) -> Result<(), ActionError> {
+        if args.len() > self.arguments.len() {
+            return Err(ActionError::Validation {
+                field: "arguments",
+                reason: "too many",
+            });
+        }
+
+        for (index, argument) in self.arguments.iter().enumerate() {
+            let value = args.get(index).ok_or(ActionError::missing(argument.name))?;
+
+            if argument.kind == ArgumentKind::Id && value.trim().parse::<u32>().is_err() {
+                return Err(ActionError::InvalidId(value.clone()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::ActionError, task_manager::TaskManager};
+
+    use super::CustomAction;
+
+    #[test]
+    fn test_run_checks_arguments() {
+        let mut manager = TaskManager::new();
+
+        let action = CustomAction::new("rename", "Renames a task", |manager, args| {
+            let id = args[0].trim().parse().unwrap();
+            let description = manager.get_by_id(id)?.description.clone();
+
+            manager.update_by(id, &args[1], &description)
+        })
+        .with_id("id", "Task to rename")
+        .with_text("name", "New name");
+
+        let added = manager.add("Name", "Description").unwrap();
+        let id = added.id.to_string();
+
+        assert_eq!(
+            action.run(&mut manager, std::slice::from_ref(&id)),
+            Err(ActionError::missing("name"))
+        );
+        assert_eq!(
+            action.run(&mut manager, &["x".to_string(), "Other".to_string()]),
+            Err(ActionError::InvalidId("x".to_string()))
+        );
+        assert!(action.run(&mut manager, &[id, "Other".to_string()]).is_ok());
+        assert_eq!(manager.get_by_id(added.id).unwrap().name, "Other");
+    }
+}
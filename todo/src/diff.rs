@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use crate::{task::Task, task_manager::TaskManager};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    Added(Task),
+    Removed(Task),
+    Updated {
+        before: Task,
+        after: Task,
+    },
+    /// `from` and `to` are places in the list, starting at 1.
+    Moved {
+        task: Task,
+        from: usize,
+        to: usize,
+    },
+}
+
+/// What changed between two versions of a list, task by task.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Diff {
+    pub changes: Vec<Change>,
+}
+
+impl Diff {
+    pub fn between(before: &TaskManager, after: &TaskManager) -> Self {
+        let old: HashMap<u32, (usize, &Task)> = before
+            .get_tasks()
+            .enumerate()
+            .map(|(index, task)| (task.id, (index, task)))
+            .collect();
+        let new: Vec<&Task> = after.get_tasks().collect();
+
+        let mut changes: Vec<Change> = before
+            .get_tasks()
+            .filter(|task| after.get_by_id(task.id).is_err())
+            .map(|task| Change::Removed(task.clone()))
+            .collect();
+
+        for task in &new {
+            match old.get(&task.id) {
+                None => changes.push(Change::Added((*task).clone())),
+                Some((_, previous)) if !same_content(previous, task) => {
+                    changes.push(Change::Updated {
+                        before: (*previous).clone(),
+                        after: (*task).clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        changes.extend(moved(&old, &new));
+
+        Self { changes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+fn same_content(first: &Task, second: &Task) -> bool {
+    first.name == second.name
+        && first.description == second.description
+        && first.done == second.done
+        && first.priority == second.priority
+        && first.projects == second.projects
+        && first.contexts == second.contexts
+        && first.attributes == second.attributes
+}
+
+/// Tasks kept in the same relative order are left alone, so moving one
+/// task to the top reports that task only and not every task it passed.
+/// The ones that stay are the longest run of kept tasks whose old places
+/// keep increasing.
+fn moved(old: &HashMap<u32, (usize, &Task)>, new: &[&Task]) -> Vec<Change> {
+    // (new place, old place) of every task that was already there.
+    let kept: Vec<(usize, usize)> = new
+        .iter()
+        .enumerate()
+        .filter_map(|(index, task)| old.get(&task.id).map(|(from, _)| (index, *from)))
+        .collect();
+
+    // Patience sorting: `tails[length]` is the index in `kept` of the
+    // smallest old place ending a run of `length + 1` increasing places.
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = vec![None; kept.len()];
+
+    for (index, (_, from)) in kept.iter().enumerate() {
+        let length = tails.partition_point(|&tail| kept[tail].1 < *from);
+
+        previous[index] = length.checked_sub(1).map(|length| tails[length]);
+
+        if length == tails.len() {
+            tails.push(index);
+        } else {
+            tails[length] = index;
+        }
+    }
+
+    let mut stays = vec![false; kept.len()];
+    let mut current = tails.last().copied();
+
+    while let Some(index) = current {
+        stays[index] = true;
+        current = previous[index];
+    }
+
+    kept.iter()
+        .zip(stays)
+        .filter(|(_, stays)| !stays)
+        .map(|((to, from), _)| Change::Moved {
+            task: new[*to].clone(),
+            from: from + 1,
+            to: to + 1,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::task_manager::{Placement, TaskManager};
+
+    use super::{Change, Diff};
+
+    #[test]
+    fn test_diff() {
+        let mut before = TaskManager::new();
+
+        let ids: Vec<u32> = ["First", "Second", "Third", "Fourth"]
+            .iter()
+            .map(|name| before.add(name, "Description").unwrap().id)
+            .collect();
+
+        let mut after = before.clone();
+
+        assert!(Diff::between(&before, &after).is_empty());
+
+        after.move_by(ids[3], Placement::Top).unwrap();
+        after.remove_by(ids[1]).unwrap();
+        after.complete_by(ids[0]).unwrap();
+
+        let diff = Diff::between(&before, &after);
+
+        assert_eq!(diff.changes.len(), 3);
+        assert!(matches!(&diff.changes[0], Change::Removed(task) if task.id == ids[1]));
+        assert!(matches!(&diff.changes[1], Change::Updated { after, .. } if after.done));
+        assert!(matches!(
+            &diff.changes[2],
+            Change::Moved { task, from: 4, to: 1 } if task.id == ids[3]
+        ));
+    }
+}
@@ -0,0 +1,81 @@
+use std::fmt::Display;
+
+use crate::task::Task;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ActionError {
+    NotFound(u32),
+    InvalidId(String),
+    Validation {
+        field: &'static str,
+        reason: &'static str,
+    },
+    UnknownCommand(String),
+    Storage(String),
+    /// Something the action needed, such as a file, couldn't be read.
+    Unreadable(String),
+    Rejected(String),
+    TransactionOpen,
+    NoTransaction,
+}
+
+impl ActionError {
+    pub fn empty(field: &'static str) -> Self {
+        ActionError::Validation {
+            field,
+            reason: "empty",
+        }
+    }
+
+    pub fn missing(field: &'static str) -> Self {
+        ActionError::Validation {
+            field,
+            reason: "missing",
+        }
+    }
+}
+
+impl Display for ActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActionError::NotFound(id) => write!(f, "Task {} not found", id),
+            ActionError::InvalidId(id) => write!(f, "'{}' is not a valid task id", id),
+            ActionError::Validation { field, reason } => write!(f, "Invalid {}: {}", field, reason),
+            ActionError::UnknownCommand(command) => write!(f, "Unknown command '{}'", command),
+            ActionError::Storage(message) => write!(f, "Storage error: {}", message),
+            ActionError::Unreadable(message) => write!(f, "Couldn't read {}", message),
+            ActionError::Rejected(reason) => write!(f, "Rejected: {}", reason),
+            ActionError::TransactionOpen => write!(f, "A transaction is already open"),
+            ActionError::NoTransaction => write!(f, "There is no open transaction"),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+/// Why a repository couldn't load the tasks.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadError {
+    /// The storage couldn't be read at all.
+    Unreadable(String),
+    /// The storage was read but isn't valid. `salvaged` holds the tasks
+    /// that could still be made out of it.
+    Corrupt { reason: String, salvaged: Vec<Task> },
+    /// The storage is in a format this version can't read, likely written
+    /// by a newer one. It must be left as it is.
+    Unsupported(String),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Unreadable(reason) => write!(f, "The tasks couldn't be read: {}", reason),
+            LoadError::Corrupt { reason, .. } => write!(f, "The tasks are corrupt: {}", reason),
+            LoadError::Unsupported(reason) => {
+                write!(f, "The tasks are in an unsupported format: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
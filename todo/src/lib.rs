@@ -1,7 +1,18 @@
 pub mod action_args;
 pub mod action_manager;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod command;
+pub mod custom_action;
+pub mod diff;
 pub mod display;
+pub mod error;
+pub mod middleware;
+pub mod outcome;
 pub mod reader;
 pub mod repository;
+pub mod shared;
+pub mod similarity;
+pub mod statistics;
 pub mod task;
 pub mod task_manager;
@@ -0,0 +1,21 @@
+use crate::{command::Command, error::ActionError, outcome::Outcome, task_manager::TaskManager};
+
+/// Code that runs around every command given to the `ActionManger`.
+///
+/// `before` sees the command before it runs and can change it, or stop it
+/// by returning an error, which is then returned by `process`. `after` sees
+/// the command with its result once it ran or was stopped by a middleware
+/// added later.
+pub trait Middleware: Send {
+    fn before(&mut self, _command: &mut Command, _tasks: &TaskManager) -> Result<(), ActionError> {
+        Ok(())
+    }
+
+    fn after(
+        &mut self,
+        _command: &Command,
+        _result: &Result<Outcome, ActionError>,
+        _tasks: &TaskManager,
+    ) {
+    }
+}
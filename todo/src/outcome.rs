@@ -0,0 +1,22 @@
+use crate::{statistics::Statistics, task::Task};
+
+/// What an action produced, for the frontend to show as it sees fit.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// Nothing to show, like after opening a transaction.
+    Done,
+    /// The task that was added, with the ids of the open tasks that look
+    /// like a duplicate of it. Those are only looked for by a manager
+    /// built `with_duplicate_check`.
+    Added {
+        task: Task,
+        similar: Vec<u32>,
+    },
+    /// The task that was changed or removed.
+    Task(Task),
+    /// Every task, in order.
+    Tasks(Vec<Task>),
+    Statistics(Statistics),
+    /// How many tasks were saved.
+    Saved(usize),
+}
@@ -1,3 +1,4 @@
 pub trait Reader: 'static {
-    fn read(&mut self) -> String;
+    /// Returns the next line, or `None` once the input is over.
+    fn read(&mut self) -> Option<String>;
 }
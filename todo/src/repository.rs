@@ -1,6 +1,7 @@
-use crate::task::Task;
+use crate::{error::LoadError, task::Task};
 
-pub trait Repository {
-    fn save(&mut self, tasks: Vec<Task>) -> bool;
-    fn load(&mut self) -> Vec<Task>;
+pub trait Repository: Send {
+    fn save(&mut self, tasks: Vec<&Task>) -> bool;
+    /// Reads the stored tasks. Storage that doesn't exist yet holds none.
+    fn load(&mut self) -> Result<Vec<Task>, LoadError>;
 }
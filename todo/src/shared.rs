@@ -0,0 +1,187 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+
+use crate::{
+    action_manager::ActionManger, command::Command, error::ActionError, outcome::Outcome,
+    statistics::Statistics, task::Task,
+};
+
+/// A handle to one `ActionManger` that can be cloned and sent to other
+/// threads, so several frontends can work on the same list.
+///
+/// Actions run one at a time. Readers don't wait for them: they get the
+/// tasks as they were after the last action that finished outside of a
+/// transaction, so they never see half of a transaction. The tasks are
+/// only copied again after an action that changed them.
+#[derive(Clone)]
+pub struct SharedActionManger {
+    action_manager: Arc<Mutex<ActionManger>>,
+    /// The tasks, with the `ActionManger::revision` they were copied at.
+    snapshot: Arc<RwLock<(u64, Arc<Vec<Task>>)>>,
+}
+
+impl SharedActionManger {
+    pub fn new(action_manager: ActionManger) -> Self {
+        let snapshot = (action_manager.revision(), Arc::new(action_manager.tasks()));
+
+        Self {
+            action_manager: Arc::new(Mutex::new(action_manager)),
+            snapshot: Arc::new(RwLock::new(snapshot)),
+        }
+    }
+
+    pub fn process(&self, command: Command) -> Result<Outcome, ActionError> {
+        self.with(|action_manager| action_manager.process(command))
+    }
+
+    pub fn process_all(&self, list: Vec<Command>) -> Result<Vec<Outcome>, ActionError> {
+        self.with(|action_manager| action_manager.process_all(list))
+    }
+
+    /// Gives `f` the `ActionManger` for as long as it runs, for anything
+    /// the handle doesn't cover. Other actions wait until it returns.
+    pub fn with<R>(&self, f: impl FnOnce(&mut ActionManger) -> R) -> R {
+        let mut action_manager = self.lock();
+
+        let result = f(&mut action_manager);
+
+        let revision = action_manager.revision();
+
+        // Only `with` writes the snapshot, and it holds the manager, so it
+        // can't go stale between this check and the write.
+        let stale = self
+            .snapshot
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .0
+            != revision;
+
+        if stale && !action_manager.in_transaction() {
+            let tasks = Arc::new(action_manager.tasks());
+
+            *self
+                .snapshot
+                .write()
+                .unwrap_or_else(PoisonError::into_inner) = (revision, tasks);
+        }
+
+        result
+    }
+
+    /// Returns the tasks, in order, as they were after the last action.
+    pub fn snapshot(&self) -> Arc<Vec<Task>> {
+        self.snapshot
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .1
+            .clone()
+    }
+
+    pub fn statistics(&self) -> Statistics {
+        Statistics::new(self.snapshot().iter(), chrono::Utc::now())
+    }
+
+    // An action that panicked left the manager as it was when it stopped,
+    // which is still a valid list, so the lock is used anyway.
+    fn lock(&self) -> MutexGuard<'_, ActionManger> {
+        self.action_manager
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use crate::{
+        action_manager::ActionManger, command::Command, error::LoadError, repository::Repository,
+        task::Task,
+    };
+
+    use super::SharedActionManger;
+
+    struct Test;
+
+    impl Repository for Test {
+        fn save(&mut self, _tasks: Vec<&Task>) -> bool {
+            true
+        }
+
+        fn load(&mut self) -> Result<Vec<Task>, LoadError> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn add(name: String) -> Command {
+        Command::Add {
+            name,
+            description: "Description".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_actions_from_many_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SharedActionManger>();
+
+        let shared = SharedActionManger::new(ActionManger::new(Box::new(Test)));
+
+        let handles: Vec<_> = (0..4)
+            .map(|thread| {
+                let shared = shared.clone();
+
+                thread::spawn(move || {
+                    for task in 0..25 {
+                        let name = format!("Thread {} task {}", thread, task);
+
+                        assert!(shared.process(add(name)).is_ok());
+                        assert!(shared.snapshot().len() <= 100);
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(shared.snapshot().len(), 100);
+        assert_eq!(shared.statistics().open, 100);
+    }
+
+    #[test]
+    fn test_snapshot_skips_open_transaction() {
+        let shared = SharedActionManger::new(ActionManger::new(Box::new(Test)));
+
+        assert!(shared.process(add("First".to_string())).is_ok());
+
+        let before = shared.snapshot();
+
+        assert!(shared.process(Command::Begin).is_ok());
+        assert!(shared.process(add("Second".to_string())).is_ok());
+        assert_eq!(shared.snapshot().len(), 1);
+
+        assert!(shared.process(Command::Commit).is_ok());
+        assert_eq!(shared.snapshot().len(), 2);
+        assert_eq!(before.len(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_copied_only_after_changes() {
+        let shared = SharedActionManger::new(ActionManger::new(Box::new(Test)));
+
+        assert!(shared.process(add("First".to_string())).is_ok());
+
+        let before = shared.snapshot();
+
+        assert!(shared.process(Command::Display).is_ok());
+        assert!(shared.process(Command::Stats).is_ok());
+        assert!(shared.process(Command::Remove { id: 0 }).is_err());
+        shared.with(|action_manager| action_manager.tasks());
+        assert!(Arc::ptr_eq(&before, &shared.snapshot()));
+
+        assert!(shared.process(add("Second".to_string())).is_ok());
+        assert!(!Arc::ptr_eq(&before, &shared.snapshot()));
+        assert_eq!(shared.snapshot().len(), 2);
+    }
+}
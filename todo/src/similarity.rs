@@ -0,0 +1,189 @@
+use std::collections::HashSet;
+
+/// Names at least this similar are reported as possible duplicates.
+pub const SIMILARITY_THRESHOLD: f64 = 0.8;
+
+/// Returns how alike two task names are, from 0.0 (nothing in common) to
+/// 1.0 (same words). Case, punctuation and extra spaces are ignored, and
+/// the best of the edit distance and the shared words is used, so both
+/// typos and reordered words are caught. Names with different numbers
+/// ("Chapter 1", "Chapter 2") are never considered alike.
+pub fn similarity(first: &str, second: &str) -> f64 {
+    let first = normalize(first);
+    let second = normalize(second);
+
+    if first.is_empty() || second.is_empty() || !numbers(&first).eq(numbers(&second)) {
+        return 0.0;
+    }
+
+    edit_similarity(&first, &second).max(word_similarity(&first, &second))
+}
+
+/// A name prepared for comparing against many others: its normal form,
+/// numbers and words are worked out once, and the others are turned down
+/// before the edit distance whenever their numbers or length alone show
+/// they can't be similar enough.
+pub struct Matcher {
+    name: String,
+    length: usize,
+    numbers: Vec<String>,
+    words: HashSet<String>,
+}
+
+impl Matcher {
+    pub fn new(name: &str) -> Self {
+        let name = normalize(name);
+
+        Self {
+            length: name.chars().count(),
+            numbers: numbers(&name).map(String::from).collect(),
+            words: name.split(' ').map(String::from).collect(),
+            name,
+        }
+    }
+
+    /// Whether `similarity` would reach `SIMILARITY_THRESHOLD`.
+    pub fn is_similar(&self, other: &str) -> bool {
+        let other = normalize(other);
+
+        if self.name.is_empty() || other.is_empty() || !numbers(&other).eq(self.numbers.iter()) {
+            return false;
+        }
+
+        let words: HashSet<&str> = other.split(' ').collect();
+        let shared = words
+            .iter()
+            .filter(|word| self.words.contains(**word))
+            .count();
+        let all = self.words.len() + words.len() - shared;
+
+        if shared as f64 / all as f64 >= SIMILARITY_THRESHOLD {
+            return true;
+        }
+
+        // The edit distance is at least the difference in length.
+        let length = other.chars().count();
+        let longest = self.length.max(length) as f64;
+
+        if 1.0 - self.length.abs_diff(length) as f64 / longest < SIMILARITY_THRESHOLD {
+            return false;
+        }
+
+        edit_similarity(&self.name, &other) >= SIMILARITY_THRESHOLD
+    }
+}
+
+fn normalize(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            normalized.push(c);
+        } else if !normalized.is_empty() && !normalized.ends_with(' ') {
+            normalized.push(' ');
+        }
+    }
+
+    if normalized.ends_with(' ') {
+        normalized.pop();
+    }
+
+    normalized
+}
+
+fn numbers(text: &str) -> impl Iterator<Item = &str> {
+    text.split(' ')
+        .filter(|word| word.chars().all(|c| c.is_numeric()))
+}
+
+/// Edit distance where swapping two neighbour letters counts as one edit.
+fn edit_similarity(first: &str, second: &str) -> f64 {
+    let first: Vec<char> = first.chars().collect();
+    let second: Vec<char> = second.chars().collect();
+
+    let mut distances = vec![vec![0; second.len() + 1]; first.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+
+    distances[0] = (0..=second.len()).collect();
+
+    for i in 1..=first.len() {
+        for j in 1..=second.len() {
+            let cost = if first[i - 1] == second[j - 1] { 0 } else { 1 };
+
+            let mut distance = (distances[i - 1][j - 1] + cost)
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+
+            if i > 1 && j > 1 && first[i - 1] == second[j - 2] && first[i - 2] == second[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+
+            distances[i][j] = distance;
+        }
+    }
+
+    let distance = distances[first.len()][second.len()];
+
+    1.0 - distance as f64 / first.len().max(second.len()) as f64
+}
+
+fn word_similarity(first: &str, second: &str) -> f64 {
+    let first: HashSet<&str> = first.split(' ').collect();
+    let second: HashSet<&str> = second.split(' ').collect();
+
+    first.intersection(&second).count() as f64 / first.union(&second).count() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{similarity, Matcher, SIMILARITY_THRESHOLD};
+
+    #[test]
+    fn test_similar_names() {
+        assert_eq!(similarity("Buy milk", "buy  MILK!"), 1.0);
+        assert!(similarity("Buy milk", "Buy mlik") >= SIMILARITY_THRESHOLD);
+        assert!(similarity("Call the bank", "the bank call") >= SIMILARITY_THRESHOLD);
+    }
+
+    #[test]
+    fn test_different_names() {
+        assert!(similarity("Buy milk", "Write report") < SIMILARITY_THRESHOLD);
+        assert!(similarity("Fix login", "Fix logout page layout") < SIMILARITY_THRESHOLD);
+        assert_eq!(similarity("Read chapter 1", "Read chapter 2"), 0.0);
+        assert_eq!(similarity("", "Buy milk"), 0.0);
+    }
+
+    #[test]
+    fn test_matcher_agrees_with_similarity() {
+        let names = [
+            "Buy milk",
+            "buy  MILK!",
+            "Buy mlik",
+            "Call the bank",
+            "the bank call",
+            "Write report",
+            "Fix login",
+            "Fix logout page layout",
+            "Read chapter 1",
+            "Read chapter 2",
+            "",
+        ];
+
+        for first in names {
+            let matcher = Matcher::new(first);
+
+            for second in names {
+                assert_eq!(
+                    matcher.is_similar(second),
+                    similarity(first, second) >= SIMILARITY_THRESHOLD,
+                    "{:?} and {:?}",
+                    first,
+                    second
+                );
+            }
+        }
+    }
+}
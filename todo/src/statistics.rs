@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, Duration, Utc};
+
+use crate::task::Task;
+
+const OLDEST_OPEN_LIMIT: usize = 5;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeeklyActivity {
+    pub year: i32,
+    pub week: u32,
+    pub created: usize,
+    pub completed: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Statistics {
+    pub total: usize,
+    pub open: usize,
+    pub done: usize,
+    pub completion_rate: f64,
+    pub weekly: Vec<WeeklyActivity>,
+    pub average_time_to_complete: Option<Duration>,
+    pub oldest_open: Vec<Task>,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl Statistics {
+    pub fn new<'a>(tasks: impl Iterator<Item = &'a Task>, now: DateTime<Utc>) -> Self {
+        let mut total = 0;
+        let mut done = 0;
+        let mut weeks: BTreeMap<(i32, u32), WeeklyActivity> = BTreeMap::new();
+        let mut durations: Vec<Duration> = Vec::new();
+        let mut open_tasks: Vec<&Task> = Vec::new();
+
+        for task in tasks {
+            total += 1;
+
+            if task.done {
+                done += 1;
+            } else {
+                open_tasks.push(task);
+            }
+
+            if let Some(created_at) = task.created_at {
+                week_of(&mut weeks, created_at).created += 1;
+            }
+
+            if let Some(completed_at) = task.completed_at {
+                week_of(&mut weeks, completed_at).completed += 1;
+
+                if let Some(created_at) = task.created_at {
+                    durations.push(completed_at - created_at);
+                }
+            }
+        }
+
+        let average_time_to_complete = match durations.len() {
+            0 => None,
+            amount => {
+                Some(durations.iter().fold(Duration::zero(), |sum, d| sum + *d) / amount as i32)
+            }
+        };
+
+        // Tasks without a creation date were stored before timestamps existed,
+        // so they are older than any dated task.
+        open_tasks.sort_by_key(|task| (task.created_at, task.id));
+
+        Self {
+            total,
+            open: total - done,
+            done,
+            completion_rate: if total == 0 {
+                0.0
+            } else {
+                done as f64 / total as f64
+            },
+            weekly: weeks.into_values().collect(),
+            average_time_to_complete,
+            oldest_open: open_tasks
+                .into_iter()
+                .take(OLDEST_OPEN_LIMIT)
+                .cloned()
+                .collect(),
+            generated_at: now,
+        }
+    }
+}
+
+fn week_of(
+    weeks: &mut BTreeMap<(i32, u32), WeeklyActivity>,
+    date: DateTime<Utc>,
+) -> &mut WeeklyActivity {
+    let week = date.iso_week();
+
+    weeks
+        .entry((week.year(), week.week()))
+        .or_insert(WeeklyActivity {
+            year: week.year(),
+            week: week.week(),
+            created: 0,
+            completed: 0,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::task::Task;
+
+    use super::Statistics;
+
+    fn task(id: u32, created: Option<(u32, u32)>, completed: Option<(u32, u32)>) -> Task {
+        let at =
+            |(month, day): (u32, u32)| Utc.with_ymd_and_hms(2024, month, day, 12, 0, 0).unwrap();
+
+        Task {
+            id,
+            name: format!("Task {}", id),
+            description: "Description".to_string(),
+            done: completed.is_some(),
+            created_at: created.map(at),
+            completed_at: completed.map(at),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_counts_and_rate() {
+        let tasks = [
+            task(1, Some((1, 1)), Some((1, 3))),
+            task(2, Some((1, 2)), None),
+            task(3, None, None),
+            task(4, Some((1, 8)), Some((1, 9))),
+        ];
+
+        let statistics = Statistics::new(tasks.iter(), Utc::now());
+
+        assert_eq!(statistics.total, 4);
+        assert_eq!(statistics.open, 2);
+        assert_eq!(statistics.done, 2);
+        assert_eq!(statistics.completion_rate, 0.5);
+        assert_eq!(
+            statistics.average_time_to_complete,
+            Some(Duration::hours(36))
+        );
+    }
+
+    #[test]
+    fn test_weekly_activity() {
+        let tasks = [
+            task(1, Some((1, 1)), Some((1, 3))),
+            task(2, Some((1, 2)), None),
+            task(3, Some((1, 8)), Some((1, 9))),
+        ];
+
+        let statistics = Statistics::new(tasks.iter(), Utc::now());
+
+        assert_eq!(statistics.weekly.len(), 2);
+        assert_eq!(statistics.weekly[0].week, 1);
+        assert_eq!(statistics.weekly[0].created, 2);
+        assert_eq!(statistics.weekly[0].completed, 1);
+        assert_eq!(statistics.weekly[1].week, 2);
+        assert_eq!(statistics.weekly[1].created, 1);
+        assert_eq!(statistics.weekly[1].completed, 1);
+    }
+
+    #[test]
+    fn test_oldest_open_first() {
+        let tasks = [
+            task(1, Some((3, 1)), None),
+            task(2, Some((1, 1)), None),
+            task(3, None, None),
+            task(4, Some((1, 1)), Some((1, 2))),
+        ];
+
+        let statistics = Statistics::new(tasks.iter(), Utc::now());
+
+        let ids: Vec<u32> = statistics.oldest_open.iter().map(|task| task.id).collect();
+
+        assert_eq!(ids, vec![3, 2, 1]);
+    }
+}
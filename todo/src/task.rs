@@ -1,64 +1,81 @@
-use std::{fmt::Display, sync::Mutex};
+use std::{collections::BTreeMap, sync::Mutex};
 
-#[derive(Clone)]
+use chrono::{DateTime, Utc};
+
+use crate::error::ActionError;
+
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Task {
     pub id: u32,
     pub name: String,
     pub description: String,
     pub done: bool,
+    pub position: u64,
+    pub created_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// From `A`, the highest, to `Z`, as in todo.txt.
+    pub priority: Option<char>,
+    /// `+project` tags, without the `+`.
+    pub projects: Vec<String>,
+    /// `@context` tags, without the `@`.
+    pub contexts: Vec<String>,
+    /// Any other `key:value` pairs, such as a due date.
+    pub attributes: BTreeMap<String, String>,
 }
 
 lazy_static::lazy_static! {
     static ref DYNAMIC_ID: Mutex <u32> = Mutex::new(0);
 }
 
-impl Display for Task {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(
-            f,
-            "{}. [{}] - {} - {}",
-            self.id,
-            if self.done == true { 'X' } else { ' ' },
-            self.name,
-            self.description
-        )
-    }
-}
-
 impl Task {
-    pub fn new(name: &str, description: &str) -> Result<Self, &'static str> {
-        if check_string(name) && check_string(description) {
-            let mut dynamic_id = DYNAMIC_ID.lock().unwrap();
-            *dynamic_id += 1;
-
-            Ok(Self {
-                id: *dynamic_id,
-                name: name.to_owned(),
-                description: description.to_owned(),
-                done: false,
-            })
-        } else {
-            Err("Cannot create a Task")
-        }
+    pub fn new(name: &str, description: &str) -> Result<Self, ActionError> {
+        check_fields(name, description)?;
+
+        let mut dynamic_id = DYNAMIC_ID.lock().unwrap();
+        *dynamic_id += 1;
+
+        Ok(Self {
+            id: *dynamic_id,
+            name: name.to_owned(),
+            description: description.to_owned(),
+            created_at: Some(Utc::now()),
+            ..Default::default()
+        })
     }
 
-    pub fn update(&mut self, name: &str, description: &str) -> Result<(), &'static str> {
-        if check_string(name) && check_string(description) {
-            self.name = name.to_owned();
-            self.description = description.to_owned();
-            self.done = false;
+    pub fn update(&mut self, name: &str, description: &str) -> Result<(), ActionError> {
+        check_fields(name, description)?;
 
-            Ok(())
-        } else {
-            Err("Cannot update the Task")
-        }
+        self.name = name.to_owned();
+        self.description = description.to_owned();
+        self.set_done(false);
+
+        Ok(())
     }
 
     pub fn set_done(&mut self, done: bool) {
+        if done && !self.done {
+            self.completed_at = Some(Utc::now());
+        } else if !done {
+            self.completed_at = None;
+        }
+
         self.done = done;
     }
 }
 
+pub(crate) fn check_fields(name: &str, description: &str) -> Result<(), ActionError> {
+    if !check_string(name) {
+        return Err(ActionError::empty("name"));
+    }
+
+    if !check_string(description) {
+        return Err(ActionError::empty("description"));
+    }
+
+    Ok(())
+}
+
 fn check_string(field: &str) -> bool {
     if field.trim().is_empty() {
         return false;
@@ -71,3 +88,17 @@ pub fn set_counter(max_id: u32) {
     let mut dynamic_id = DYNAMIC_ID.lock().unwrap();
     *dynamic_id += max_id;
 }
+
+pub(crate) fn counter() -> u32 {
+    *DYNAMIC_ID.lock().unwrap()
+}
+
+/// Gives back the ids taken since the counter was at `to`, unless some
+/// were taken by someone else in the meantime.
+pub(crate) fn reset_counter(from: u32, to: u32) {
+    let mut dynamic_id = DYNAMIC_ID.lock().unwrap();
+
+    if *dynamic_id == from {
+        *dynamic_id = to;
+    }
+}
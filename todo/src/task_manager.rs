@@ -1,78 +1,694 @@
-use crate::task::{set_counter, Task};
+use std::collections::{BTreeMap, HashMap};
 
+use chrono::Utc;
+
+use crate::{
+    error::ActionError,
+    similarity::Matcher,
+    statistics::Statistics,
+    task::{check_fields, set_counter, Task},
+};
+
+const POSITION_GAP: u64 = 1024;
+
+/// A task that was just added, with the ids of open tasks whose names are
+/// similar enough to be a duplicate of it. They are only looked for by a
+/// manager built `with_duplicate_check`.
+pub struct Added {
+    pub id: u32,
+    pub similar: Vec<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Placement {
+    Up,
+    Down,
+    Top,
+    Bottom,
+    Before(u32),
+}
+
+/// Tasks are indexed by id, and `order` maps each task position to its id.
+/// Positions are spaced by `POSITION_GAP` so a task can usually be moved
+/// by changing only its own position.
+///
+/// While a transaction is open, `snapshot` keeps the list as it was when
+/// the transaction began so it can be brought back by `rollback`.
+#[derive(Clone, Default)]
 pub struct TaskManager {
-    tasks: Vec<Task>,
+    tasks: HashMap<u32, Task>,
+    order: BTreeMap<u64, u32>,
+    snapshot: Option<(HashMap<u32, Task>, BTreeMap<u64, u32>)>,
+    check_duplicates: bool,
 }
 
 impl TaskManager {
     pub fn new() -> Self {
-        Self { tasks: Vec::new() }
+        Self {
+            tasks: HashMap::new(),
+            order: BTreeMap::new(),
+            snapshot: None,
+            check_duplicates: false,
+        }
+    }
+
+    /// Makes `add` look for open tasks with a similar name. This compares
+    /// the name against every open task, so callers that already ask
+    /// `find_similar` before adding should leave it off.
+    pub fn with_duplicate_check(mut self) -> Self {
+        self.check_duplicates = true;
+        self
+    }
+
+    /// Starts a transaction. Transactions don't nest, so this fails when
+    /// one is already open.
+    pub fn begin(&mut self) -> Result<(), ActionError> {
+        if self.snapshot.is_some() {
+            return Err(ActionError::TransactionOpen);
+        }
+
+        self.snapshot = Some((self.tasks.clone(), self.order.clone()));
+        Ok(())
+    }
+
+    pub fn commit(&mut self) -> Result<(), ActionError> {
+        match self.snapshot.take() {
+            Some(_) => Ok(()),
+            None => Err(ActionError::NoTransaction),
+        }
     }
 
-    pub fn add(&mut self, name: &str, description: &str) -> bool {
-        match Task::new(name, description) {
-            Ok(task) => {
-                self.tasks.push(task);
-                true
+    /// Brings back the list as it was when the transaction began. Ids
+    /// handed out during the transaction are not reused.
+    pub fn rollback(&mut self) -> Result<(), ActionError> {
+        match self.snapshot.take() {
+            Some((tasks, order)) => {
+                self.tasks = tasks;
+                self.order = order;
+                Ok(())
             }
+            None => Err(ActionError::NoTransaction),
+        }
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.snapshot.is_some()
+    }
+
+    pub fn add(&mut self, name: &str, description: &str) -> Result<Added, ActionError> {
+        let mut task = Task::new(name, description)?;
 
-            Err(_) => false,
+        let similar = if self.check_duplicates {
+            self.find_similar(name).iter().map(|task| task.id).collect()
+        } else {
+            Vec::new()
+        };
+
+        task.position = self.last_position() + POSITION_GAP;
+        self.order.insert(task.position, task.id);
+
+        let id = task.id;
+        self.tasks.insert(id, task);
+
+        Ok(Added { id, similar })
+    }
+
+    /// Adds `tasks` at the end of the list, in their order, with fresh ids.
+    /// Their other fields are kept. If any of them is invalid, none is
+    /// added.
+    pub fn import(&mut self, tasks: Vec<Task>) -> Result<Vec<u32>, ActionError> {
+        for task in &tasks {
+            check_fields(&task.name, &task.description)?;
         }
+
+        let mut ids = Vec::with_capacity(tasks.len());
+
+        for task in tasks {
+            let mut imported = Task::new(&task.name, &task.description)?;
+
+            imported.done = task.done;
+            imported.created_at = task.created_at.or(imported.created_at);
+            imported.completed_at = task.completed_at;
+            imported.priority = task.priority;
+            imported.projects = task.projects;
+            imported.contexts = task.contexts;
+            imported.attributes = task.attributes;
+            imported.position = self.last_position() + POSITION_GAP;
+
+            self.order.insert(imported.position, imported.id);
+            ids.push(imported.id);
+            self.tasks.insert(imported.id, imported);
+        }
+
+        Ok(ids)
     }
 
-    pub fn get_by(&mut self, index: u32) -> Result<&Task, &'static str> {
-        match self.tasks.get(index as usize) {
-            Some(task) => Ok(task),
-            None => Err("Not found"),
+    /// Open tasks whose names look like `name`, in the list order.
+    pub fn find_similar(&self, name: &str) -> Vec<&Task> {
+        let matcher = Matcher::new(name);
+
+        self.get_tasks()
+            .filter(|task| !task.done && matcher.is_similar(&task.name))
+            .collect()
+    }
+
+    pub fn get_by_id(&self, id: u32) -> Result<&Task, ActionError> {
+        self.tasks.get(&id).ok_or(ActionError::NotFound(id))
+    }
+
+    /// Looks up the task shown at `position` (starting at 0). Unlike
+    /// `get_by_id` this walks the list, so it is linear in `position`.
+    pub fn get_by_position(&self, position: usize) -> Result<&Task, ActionError> {
+        match self.order.values().nth(position) {
+            Some(id) => Ok(&self.tasks[id]),
+            None => Err(ActionError::Validation {
+                field: "position",
+                reason: "out of range",
+            }),
         }
     }
 
-    pub fn complete_by(&mut self, id: u32) -> bool {
-        self.tasks
-            .iter_mut()
-            .find(|task| task.id == id)
-            .map(|task| {
-                task.set_done(true);
-                true
-            })
-            .unwrap_or(false)
+    pub fn complete_by(&mut self, id: u32) -> Result<(), ActionError> {
+        self.get_mut(id)?.set_done(true);
+        Ok(())
+    }
+
+    pub fn update_by(&mut self, id: u32, name: &str, description: &str) -> Result<(), ActionError> {
+        self.get_mut(id)?.update(name, description)
+    }
+
+    pub fn remove_by(&mut self, id: u32) -> Result<(), ActionError> {
+        let task = self.tasks.remove(&id).ok_or(ActionError::NotFound(id))?;
+        self.order.remove(&task.position);
+        Ok(())
     }
 
-    pub fn update_by(&mut self, id: u32, name: &str, description: &str) -> bool {
-        self.tasks
-            .iter_mut()
-            .find(|task| task.id == id)
-            .map(|task| task.update(name, description).is_ok())
-            .unwrap_or(false)
+    /// Builds the task that merging `other` into `keep` would produce,
+    /// without changing the list.
+    pub fn merged(&self, keep: u32, other: u32) -> Result<Task, ActionError> {
+        if keep == other {
+            return Err(ActionError::Validation {
+                field: "id",
+                reason: "same task",
+            });
+        }
+
+        let mut task = self.get_by_id(keep)?.clone();
+        let other = self.get_by_id(other)?;
+
+        if !task
+            .description
+            .split("; ")
+            .any(|part| part.trim().eq_ignore_ascii_case(other.description.trim()))
+        {
+            task.description = format!("{}; {}", task.description, other.description);
+        }
+
+        task.created_at = match (task.created_at, other.created_at) {
+            (Some(first), Some(second)) => Some(first.min(second)),
+            (first, second) => first.or(second),
+        };
+
+        if !other.done {
+            task.set_done(false);
+        } else if task.done {
+            task.completed_at = task.completed_at.max(other.completed_at);
+        }
+
+        // The higher priority wins, and `A` sorts before `B`.
+        task.priority = match (task.priority, other.priority) {
+            (Some(first), Some(second)) => Some(first.min(second)),
+            (first, second) => first.or(second),
+        };
+
+        for project in &other.projects {
+            if !task.projects.contains(project) {
+                task.projects.push(project.clone());
+            }
+        }
+
+        for context in &other.contexts {
+            if !task.contexts.contains(context) {
+                task.contexts.push(context.clone());
+            }
+        }
+
+        for (key, value) in &other.attributes {
+            task.attributes
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+
+        Ok(task)
     }
 
-    pub fn remove_by(&mut self, id: u32) -> bool {
-        let len = self.tasks.len();
+    /// Merges `other` into `keep`, which stays in its place, and removes
+    /// `other` from the list.
+    pub fn merge(&mut self, keep: u32, other: u32) -> Result<(), ActionError> {
+        let task = self.merged(keep, other)?;
 
-        if let Some(position) = self.tasks.iter().position(|task| task.id == id) {
-            self.tasks.remove(position);
+        self.tasks.insert(keep, task);
+        self.remove_by(other)
+    }
+
+    pub fn move_by(&mut self, id: u32, placement: Placement) -> Result<(), ActionError> {
+        let position = self.get_by_id(id)?.position;
+
+        match placement {
+            Placement::Up => {
+                if let Some((&previous, _)) = self.order.range(..position).next_back() {
+                    self.swap(position, previous);
+                }
+            }
+            Placement::Down => {
+                if let Some((&next, _)) = self.order.range(position + 1..).next() {
+                    self.swap(position, next);
+                }
+            }
+            Placement::Top => {
+                self.order.remove(&position);
+                let first = self.order.keys().next().copied();
+                self.place(id, None, first);
+            }
+            Placement::Bottom => {
+                self.order.remove(&position);
+                let last = self.order.keys().next_back().copied();
+                self.place(id, last, None);
+            }
+            Placement::Before(target) => {
+                if target == id {
+                    return Err(ActionError::Validation {
+                        field: "target",
+                        reason: "same task",
+                    });
+                }
+
+                let next = self.get_by_id(target)?.position;
+
+                self.order.remove(&position);
+                let previous = self.order.range(..next).next_back().map(|(p, _)| *p);
+                self.place(id, previous, Some(next));
+            }
         }
 
-        len > self.tasks.len()
+        Ok(())
     }
 
     pub fn get_amount(&self) -> u32 {
         self.tasks.len() as u32
     }
 
-    pub fn get_tasks(&self) -> &Vec<Task> {
-        &self.tasks
+    pub fn get_tasks(&self) -> impl Iterator<Item = &Task> {
+        self.order.values().map(|id| &self.tasks[id])
     }
 
-    pub fn get_tasks_store(&self) -> Vec<Task> {
-        self.tasks.clone()
+    pub fn get_tasks_store(&self) -> Vec<&Task> {
+        self.get_tasks().collect()
     }
 
+    pub fn statistics(&self) -> Statistics {
+        Statistics::new(self.get_tasks(), Utc::now())
+    }
+
+    /// Keeps the stored order of `tasks` (tasks saved without a position
+    /// stay in the order they were read). Positions are kept as they were
+    /// saved, so a repository that compares against what it stored sees
+    /// them unchanged, unless some are missing or clash: then they are all
+    /// spaced again.
     pub fn set_tasks(&mut self, tasks: Vec<Task>) {
-        self.tasks = tasks;
+        let mut tasks = tasks;
+        tasks.sort_by_key(|task| task.position);
+
+        let keep = tasks.first().is_some_and(|task| task.position > 0)
+            && tasks
+                .windows(2)
+                .all(|pair| pair[0].position < pair[1].position);
+
+        self.tasks = HashMap::with_capacity(tasks.len());
+        self.order = BTreeMap::new();
+
+        for mut task in tasks {
+            if !keep {
+                task.position = self.last_position() + POSITION_GAP;
+            }
 
-        if let Some(value) = self.tasks.iter().max_by_key(|task| task.id) {
-            set_counter(value.id.clone() as u32);
+            self.order.insert(task.position, task.id);
+            self.tasks.insert(task.id, task);
         }
+
+        if let Some(id) = self.tasks.keys().max() {
+            set_counter(*id);
+        }
+    }
+
+    fn get_mut(&mut self, id: u32) -> Result<&mut Task, ActionError> {
+        self.tasks.get_mut(&id).ok_or(ActionError::NotFound(id))
+    }
+
+    fn last_position(&self) -> u64 {
+        self.order.keys().next_back().copied().unwrap_or(0)
+    }
+
+    fn swap(&mut self, first: u64, second: u64) {
+        let first_id = self.order[&first];
+        let second_id = self.order[&second];
+
+        self.order.insert(first, second_id);
+        self.order.insert(second, first_id);
+        self.tasks.get_mut(&first_id).unwrap().position = second;
+        self.tasks.get_mut(&second_id).unwrap().position = first;
+    }
+
+    /// Places a task that was taken out of `order` between two neighbours,
+    /// spacing every position again when there is no room left between them.
+    fn place(&mut self, id: u32, previous: Option<u64>, next: Option<u64>) {
+        let lower = previous.unwrap_or(0);
+
+        let position = match next {
+            Some(next) if next - lower > 1 => lower + (next - lower) / 2,
+            Some(next) => {
+                let after = self.order.get(&next).copied();
+                self.respace();
+
+                let next = self.tasks[&after.unwrap()].position;
+                next - POSITION_GAP / 2
+            }
+            None => lower + POSITION_GAP,
+        };
+
+        self.order.insert(position, id);
+        self.tasks.get_mut(&id).unwrap().position = position;
+    }
+
+    fn respace(&mut self) {
+        let ids: Vec<u32> = self.order.values().copied().collect();
+
+        self.order = ids
+            .into_iter()
+            .enumerate()
+            .map(|(index, id)| {
+                let position = (index as u64 + 1) * POSITION_GAP;
+                self.tasks.get_mut(&id).unwrap().position = position;
+                (position, id)
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::ActionError, task::Task};
+
+    use super::{Placement, TaskManager};
+
+    fn ids(manager: &TaskManager) -> Vec<u32> {
+        manager.get_tasks().map(|task| task.id).collect()
+    }
+
+    fn manager_with(ids: &[u32]) -> TaskManager {
+        let mut manager = TaskManager::new();
+
+        manager.set_tasks(
+            ids.iter()
+                .map(|id| Task {
+                    id: *id,
+                    name: format!("Task {}", id),
+                    description: "Description".to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+        );
+
+        manager
+    }
+
+    #[test]
+    fn test_get_by_id_and_position() {
+        let manager = manager_with(&[3, 7, 9]);
+
+        assert_eq!(manager.get_by_id(7).unwrap().name, "Task 7");
+        assert!(manager.get_by_id(1).is_err());
+        assert_eq!(manager.get_by_position(0).unwrap().id, 3);
+        assert_eq!(manager.get_by_position(2).unwrap().id, 9);
+        assert_eq!(
+            manager.get_by_position(3).unwrap_err(),
+            ActionError::Validation {
+                field: "position",
+                reason: "out of range"
+            }
+        );
+    }
+
+    #[test]
+    fn test_remove_keeps_order() {
+        let mut manager = manager_with(&[1, 2, 3, 4]);
+
+        assert!(manager.remove_by(2).is_ok());
+        assert_eq!(manager.remove_by(2), Err(ActionError::NotFound(2)));
+
+        assert_eq!(ids(&manager), vec![1, 3, 4]);
+        assert_eq!(manager.get_amount(), 3);
+    }
+
+    #[test]
+    fn test_complete_and_update_by_id() {
+        let mut manager = manager_with(&[1, 2]);
+
+        assert!(manager.complete_by(2).is_ok());
+        assert!(manager.complete_by(5).is_err());
+        assert!(manager.get_by_id(2).unwrap().done);
+
+        assert!(manager.update_by(1, "Name", "Other").is_ok());
+        assert_eq!(
+            manager.update_by(1, "", "Other"),
+            Err(ActionError::empty("name"))
+        );
+        assert_eq!(
+            manager.update_by(1, "Name", " "),
+            Err(ActionError::empty("description"))
+        );
+        assert_eq!(manager.get_by_id(1).unwrap().description, "Other");
+    }
+
+    #[test]
+    fn test_add_reports_similar_open_tasks() {
+        let mut manager = manager_with(&[1, 2]).with_duplicate_check();
+        manager.complete_by(2).unwrap();
+
+        let added = manager.add("task 1!", "Description").unwrap();
+        assert_eq!(added.similar, vec![1]);
+
+        let added = manager.add("Task 2", "Description").unwrap();
+        assert!(added.similar.is_empty());
+
+        assert!(manager.add("", "Description").is_err());
+
+        let mut unchecked = manager_with(&[1]);
+        assert!(unchecked
+            .add("Task 1", "Description")
+            .unwrap()
+            .similar
+            .is_empty());
+        assert_eq!(unchecked.find_similar("Task 1").len(), 2);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut manager = manager_with(&[1, 2, 3]);
+        manager.update_by(3, "Task 3", "Other").unwrap();
+        manager.complete_by(1).unwrap();
+
+        let merged = manager.merged(1, 3).unwrap();
+        assert_eq!(merged.description, "Description; Other");
+        assert!(!merged.done);
+        assert_eq!(manager.get_amount(), 3);
+
+        assert!(manager.merge(1, 3).is_ok());
+        assert_eq!(ids(&manager), vec![1, 2]);
+        assert_eq!(
+            manager.get_by_id(1).unwrap().description,
+            "Description; Other"
+        );
+
+        assert!(manager.merge(1, 2).is_ok());
+        assert_eq!(
+            manager.get_by_id(1).unwrap().description,
+            "Description; Other"
+        );
+
+        assert!(manager.merge(1, 1).is_err());
+        assert!(manager.merge(1, 2).is_err());
+    }
+
+    #[test]
+    fn test_merge_tags() {
+        let mut manager = TaskManager::new();
+        let mut tasks: Vec<Task> = manager_with(&[1, 2]).get_tasks().cloned().collect();
+
+        tasks[0].priority = Some('B');
+        tasks[0].projects = vec!["home".to_string()];
+        tasks[0]
+            .attributes
+            .insert("due".to_string(), "2024-01-01".to_string());
+        tasks[1].priority = Some('A');
+        tasks[1].projects = vec!["home".to_string(), "garden".to_string()];
+        tasks[1].contexts = vec!["phone".to_string()];
+        tasks[1]
+            .attributes
+            .insert("due".to_string(), "2024-02-01".to_string());
+        tasks[1]
+            .attributes
+            .insert("rec".to_string(), "1w".to_string());
+        manager.set_tasks(tasks);
+
+        let merged = manager.merged(1, 2).unwrap();
+        assert_eq!(merged.priority, Some('A'));
+        assert_eq!(merged.projects, vec!["home", "garden"]);
+        assert_eq!(merged.contexts, vec!["phone"]);
+        assert_eq!(merged.attributes["due"], "2024-01-01");
+        assert_eq!(merged.attributes["rec"], "1w");
+    }
+
+    #[test]
+    fn test_transaction() {
+        let mut manager = manager_with(&[1, 2, 3]);
+
+        assert_eq!(manager.commit(), Err(ActionError::NoTransaction));
+        assert_eq!(manager.rollback(), Err(ActionError::NoTransaction));
+
+        assert!(manager.begin().is_ok());
+        assert_eq!(manager.begin(), Err(ActionError::TransactionOpen));
+        assert!(manager.in_transaction());
+
+        manager.remove_by(2).unwrap();
+        manager.move_by(3, Placement::Top).unwrap();
+        manager.complete_by(1).unwrap();
+
+        assert!(manager.rollback().is_ok());
+        assert!(!manager.in_transaction());
+        assert_eq!(ids(&manager), vec![1, 2, 3]);
+        assert!(!manager.get_by_id(1).unwrap().done);
+
+        assert!(manager.begin().is_ok());
+        manager.remove_by(2).unwrap();
+        assert!(manager.commit().is_ok());
+        assert!(manager.rollback().is_err());
+        assert_eq!(ids(&manager), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_move() {
+        let mut manager = manager_with(&[1, 2, 3, 4]);
+
+        assert!(manager.move_by(3, Placement::Up).is_ok());
+        assert_eq!(ids(&manager), vec![1, 3, 2, 4]);
+
+        assert!(manager.move_by(1, Placement::Down).is_ok());
+        assert_eq!(ids(&manager), vec![3, 1, 2, 4]);
+
+        assert!(manager.move_by(4, Placement::Top).is_ok());
+        assert_eq!(ids(&manager), vec![4, 3, 1, 2]);
+
+        assert!(manager.move_by(4, Placement::Bottom).is_ok());
+        assert_eq!(ids(&manager), vec![3, 1, 2, 4]);
+
+        assert!(manager.move_by(4, Placement::Before(1)).is_ok());
+        assert_eq!(ids(&manager), vec![3, 4, 1, 2]);
+
+        assert!(manager.move_by(4, Placement::Before(4)).is_err());
+        assert_eq!(
+            manager.move_by(4, Placement::Before(9)),
+            Err(ActionError::NotFound(9))
+        );
+        assert_eq!(
+            manager.move_by(9, Placement::Top),
+            Err(ActionError::NotFound(9))
+        );
+        assert_eq!(ids(&manager), vec![3, 4, 1, 2]);
+    }
+
+    #[test]
+    fn test_move_without_room_between_positions() {
+        let mut manager = manager_with(&[1, 2, 3]);
+
+        for _ in 0..40 {
+            assert!(manager.move_by(3, Placement::Before(2)).is_ok());
+            assert!(manager.move_by(2, Placement::Before(3)).is_ok());
+        }
+
+        assert_eq!(ids(&manager), vec![1, 2, 3]);
+
+        for _ in 0..40 {
+            assert!(manager.move_by(3, Placement::Top).is_ok());
+            assert!(manager.move_by(1, Placement::Top).is_ok());
+        }
+
+        assert_eq!(ids(&manager), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn test_positions_survive_reload() {
+        let mut manager = manager_with(&[1, 2, 3]);
+
+        manager.move_by(3, Placement::Top).unwrap();
+        manager.remove_by(1).unwrap();
+
+        let stored: Vec<Task> = manager.get_tasks_store().into_iter().cloned().collect();
+        let reloaded = {
+            let mut reloaded = TaskManager::new();
+            reloaded.set_tasks(stored.iter().rev().cloned().collect());
+            reloaded
+        };
+
+        assert_eq!(ids(&reloaded), vec![3, 2]);
+        assert_eq!(reloaded.get_by_position(1).unwrap().id, 2);
+        assert_eq!(
+            reloaded.get_tasks_store(),
+            stored.iter().collect::<Vec<_>>()
+        );
+
+        // Clashing positions are spaced again, keeping the order read.
+        let mut clashing = stored.clone();
+        clashing[1].position = clashing[0].position;
+
+        let mut reloaded = TaskManager::new();
+        reloaded.set_tasks(clashing);
+
+        assert_eq!(ids(&reloaded), vec![3, 2]);
+        assert!(reloaded.get_by_id(3).unwrap().position < reloaded.get_by_id(2).unwrap().position);
+    }
+
+    #[test]
+    fn test_import() {
+        let mut manager = manager_with(&[1]);
+
+        let mut done = Task::new("Done", "Description").unwrap();
+        done.done = true;
+        done.completed_at = done.created_at;
+
+        let ids = manager
+            .import(vec![
+                done.clone(),
+                Task::new("Open", "Description").unwrap(),
+            ])
+            .unwrap();
+
+        assert_eq!(manager.get_tasks().count(), 3);
+        assert!(!ids.contains(&done.id));
+
+        let imported = manager.get_by_id(ids[0]).unwrap();
+        assert_eq!(imported.name, "Done");
+        assert!(imported.done);
+        assert_eq!(imported.completed_at, done.completed_at);
+        assert_eq!(manager.get_by_position(2).unwrap().id, ids[1]);
+
+        let mut invalid = Task::new("Invalid", "Description").unwrap();
+        invalid.name.clear();
+
+        assert_eq!(
+            manager.import(vec![Task::new("Valid", "Description").unwrap(), invalid]),
+            Err(ActionError::empty("name"))
+        );
+        assert_eq!(manager.get_tasks().count(), 3);
     }
 }